@@ -0,0 +1,379 @@
+use std::collections::BTreeSet;
+use std::io::{stdin, stdout, BufRead, Write};
+use ram_machine::instructions::{ImmediateValue, InstructionLocation, MemoryLocation};
+use ram_machine::processor::Processor;
+use ram_machine::program::{Program, SymbolKind};
+use ram_machine::snapshot::Snapshot;
+use ram_machine::tape::Tape;
+use ram_machine::value::Value;
+use ram_machine::watchpoint::{Access, Comparison, WatchCondition, WatchHit, Watchpoint};
+
+const HELP: &str = "\
+commands:
+  step [count]              execute one (or count) instructions (aliases: s, next, n)
+  continue                  execute until breakpoint, halt or error (alias: c)
+  step-back [count]         undo one (or count) executed instructions (alias: sb)
+  reverse-continue          undo executed instructions until breakpoint or start of history (alias: rc)
+  goto <step>               go back to the state after executing provided number of instructions (alias: g)
+  break <index|label>       set breakpoint on instruction index or label (alias: b)
+  delete <index|label>      remove breakpoint (alias: d)
+  breakpoints               list breakpoints (alias: bl)
+  watch <cell> [read|write|<op> <value>]
+                            pause when memory cell is read, written (default) or written with value
+                            satisfying condition, where op is one of <, <=, ==, !=, >, >= (alias: wa)
+  unwatch <number>          remove watchpoint with provided number (alias: uw)
+  watchpoints               list watchpoints (alias: wl)
+  print <cell> [n]          print memory cell (or n cells starting from it) (alias: p)
+  set <cell> <value>        set memory cell to value
+  symbols                   list symbols defined with .equ and .const (alias: sy)
+  save <file>               save snapshot of the machine to file
+  load <file>               restore the machine from snapshot saved in file
+  where                     show instruction about to be executed (alias: w)
+  list                      show source code around instruction about to be executed (alias: l)
+  help                      show this message (alias: h)
+  quit                      stop debugging (alias: q)
+cell is an address, a symbol defined with .equ or acc (the accumulator)
+empty line repeats the last command";
+
+// where the value of memory cell should be taken from
+enum Cell {
+    Accumulator,
+    Address(MemoryLocation)
+}
+
+pub struct Debugger<'a> {
+    program: &'a Program,
+    source: Vec<&'a str>,
+    breakpoints: BTreeSet<InstructionLocation>
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(program: &'a Program, source: &'a str) -> Self {
+        Debugger {
+            program,
+            source: source.lines().collect(),
+            breakpoints: BTreeSet::new()
+        }
+    }
+
+    pub fn run<T: Tape>(&mut self, processor: &mut Processor<T>) {
+        println!("debugger: type \"help\" to list available commands");
+        self.show_location(processor);
+        let mut last_command = String::new();
+        loop {
+            print!("(rasm) ");
+            let _ = stdout().flush();
+            let command = match stdin().lock().lines().next() {
+                Some(Ok(line)) => line.trim().to_string(),
+                _ => break
+            };
+            let command = if command.is_empty() { last_command.clone() } else { command };
+            if command.is_empty() { continue; }
+            match self.execute_command(processor, &command) {
+                Ok(true) => {},
+                Ok(false) => break,
+                Err(message) => println!("error: {}", message)
+            }
+            last_command = command;
+        }
+    }
+
+    // returns Ok(false) if debugging should end
+    fn execute_command<T: Tape>(&mut self, processor: &mut Processor<T>, command: &str) -> Result<bool, String> {
+        let tokens: Vec<&str> = command.split_whitespace().collect();
+        let arguments = &tokens[1..];
+        match tokens[0] {
+            "step" | "s" | "next" | "n" => {
+                let count = match arguments.first() {
+                    Some(count) => count.parse::<u64>().map_err(|_| format!("incorrect instruction count {}", count))?,
+                    None => 1
+                };
+                self.execute(processor, Some(count));
+            },
+            "continue" | "c" => self.execute(processor, None),
+            "step-back" | "sb" => {
+                let count = match arguments.first() {
+                    Some(count) => count.parse::<u64>().map_err(|_| format!("incorrect instruction count {}", count))?,
+                    None => 1
+                };
+                self.execute_backwards(processor, Some(count))?;
+            },
+            "reverse-continue" | "rc" => self.execute_backwards(processor, None)?,
+            "goto" | "g" => {
+                let step = match arguments.first() {
+                    Some(step) => step.parse::<u64>().map_err(|_| format!("incorrect step number {}", step))?,
+                    None => return Err("no step number provided".to_string())
+                };
+                if step > processor.get_step_count() {
+                    return Err(format!("step {} is not executed yet (current step is {})", step, processor.get_step_count()));
+                }
+                if !processor.rewind_to(step) {
+                    return Err(format!("step {} is no longer recorded (the earliest is {})", step, processor.get_history_start()));
+                }
+                self.show_location(processor);
+            },
+            "break" | "b" => {
+                let location = self.resolve_location(arguments)?;
+                self.breakpoints.insert(location);
+                println!("breakpoint set at instruction {}", self.describe_location(location));
+            },
+            "delete" | "d" => {
+                let location = self.resolve_location(arguments)?;
+                if !self.breakpoints.remove(&location) {
+                    return Err(format!("no breakpoint at instruction {}", location));
+                }
+                println!("breakpoint removed from instruction {}", self.describe_location(location));
+            },
+            "breakpoints" | "bl" => {
+                if self.breakpoints.is_empty() { println!("no breakpoints set"); }
+                for location in self.breakpoints.iter() {
+                    println!("  {}", self.describe_location(*location));
+                }
+            },
+            "watch" | "wa" => {
+                let address = match self.parse_cell(arguments.first())? {
+                    Cell::Accumulator => 0,
+                    Cell::Address(address) => address
+                };
+                let condition = match arguments.get(1..) {
+                    Some([]) | Some(["write"]) | None => WatchCondition::Write,
+                    Some(["read"]) => WatchCondition::Read,
+                    Some([comparison, value]) => {
+                        let comparison = parse_comparison(comparison)?;
+                        let value = value.parse::<ImmediateValue>().map_err(|_| format!("incorrect value {}", value))?;
+                        WatchCondition::Value(comparison, value)
+                    },
+                    Some(_) => return Err("incorrect watchpoint condition".to_string())
+                };
+                let watchpoint = Watchpoint { address, condition };
+                println!("watchpoint {} set on {}", processor.get_watchpoints().len(), watchpoint);
+                processor.add_watchpoint(watchpoint);
+            },
+            "unwatch" | "uw" => {
+                let index = match arguments.first() {
+                    Some(index) => index.parse::<usize>().map_err(|_| format!("incorrect watchpoint number {}", index))?,
+                    None => return Err("no watchpoint number provided".to_string())
+                };
+                match processor.remove_watchpoint(index) {
+                    Some(watchpoint) => println!("watchpoint on {} removed", watchpoint),
+                    None => return Err(format!("no watchpoint with number {}", index))
+                }
+            },
+            "watchpoints" | "wl" => {
+                if processor.get_watchpoints().is_empty() { println!("no watchpoints set"); }
+                for (index, watchpoint) in processor.get_watchpoints().iter().enumerate() {
+                    println!("  {}: {}", index, watchpoint);
+                }
+            },
+            "print" | "p" => {
+                let cell = self.parse_cell(arguments.first())?;
+                let count = match arguments.get(1) {
+                    Some(count) => count.parse::<usize>().map_err(|_| format!("incorrect cell count {}", count))?,
+                    None => 1
+                };
+                let start = match cell {
+                    Cell::Accumulator => 0,
+                    Cell::Address(address) => address
+                };
+                for address in start..start.saturating_add(count) {
+                    if !processor.contains_address(address) {
+                        return Err(format!("address {} is past the end of memory", address));
+                    }
+                    println!("  {} = {}", self.describe_cell(address), processor.get_memory_cell(address));
+                }
+            },
+            "symbols" | "sy" => {
+                if self.program.symbols.is_empty() { println!("no symbols defined"); }
+                for symbol in self.program.symbols.iter() {
+                    match symbol.kind {
+                        SymbolKind::Address => match symbol.value.to_address().filter(|x| processor.contains_address(*x)) {
+                            Some(address) => println!("  {} = [{}] = {}", symbol.name, address, processor.get_memory_cell(address)),
+                            None => println!("  {} = [{}]", symbol.name, symbol.value)
+                        },
+                        SymbolKind::Constant => println!("  {} = ={}", symbol.name, symbol.value)
+                    }
+                }
+            },
+            "set" => {
+                let cell = self.parse_cell(arguments.first())?;
+                let value = match arguments.get(1) {
+                    Some(value) => value.parse::<ImmediateValue>().map_err(|_| format!("incorrect value {}", value))?,
+                    None => return Err("no value provided".to_string())
+                };
+                match cell {
+                    Cell::Accumulator => processor.set_accumulator(value),
+                    Cell::Address(address) => {
+                        if !processor.set_memory_cell(address, value) {
+                            return Err(format!("address {} is past the end of memory", address));
+                        }
+                    }
+                }
+            },
+            "save" => {
+                let path = arguments.first().ok_or("no file provided")?;
+                processor.snapshot().save(path).map_err(|x| x.to_string())?;
+                println!("snapshot saved to {} at step {}", path, processor.get_step_count());
+            },
+            "load" => {
+                let path = arguments.first().ok_or("no file provided")?;
+                let snapshot = Snapshot::load(path).map_err(|x| x.to_string())?;
+                if snapshot.instructions != self.program.instructions {
+                    return Err("snapshot was taken from a different program".to_string());
+                }
+                let unmoved_tapes = processor.restore(snapshot);
+                println!("snapshot loaded from {} at step {}", path, processor.get_step_count());
+                for tape in unmoved_tapes {
+                    println!("warning: tape {} could not be moved back to the position recorded in snapshot, so it continues from a different value", tape);
+                }
+                self.show_location(processor);
+            },
+            "where" | "w" => self.show_location(processor),
+            "list" | "l" => self.list(processor),
+            "help" | "h" => println!("{}", HELP),
+            "quit" | "q" => return Ok(false),
+            _ => return Err(format!("unknown command {}, type \"help\" to list available commands", tokens[0]))
+        }
+        Ok(true)
+    }
+
+    // executes up to count instructions (or until halt if count is None), stopping on breakpoints and errors
+    fn execute<T: Tape>(&self, processor: &mut Processor<T>, count: Option<u64>) {
+        if processor.is_halted() {
+            println!("program is halted");
+            return;
+        }
+        let mut executed = 0u64;
+        while count.is_none_or(|count| executed < count) {
+            let result = processor.execute_instruction();
+            let watch_hits = processor.take_watch_hits();
+            self.report_watch_hits(processor, &watch_hits);
+            if let Err(error) = result {
+                println!("execution error: {}", error);
+                break;
+            }
+            executed += 1;
+            if processor.is_halted() {
+                println!("program halted after executing {} instructions", processor.get_step_count());
+                return;
+            }
+            if self.breakpoints.contains(&processor.get_instruction_pointer()) {
+                println!("breakpoint hit at instruction {}", self.describe_location(processor.get_instruction_pointer()));
+                break;
+            }
+            if !watch_hits.is_empty() { break; }
+        }
+        self.show_location(processor);
+    }
+
+    // undoes up to count instructions (or as many as recorded if count is None), stopping on breakpoints
+    fn execute_backwards<T: Tape>(&self, processor: &mut Processor<T>, count: Option<u64>) -> Result<(), String> {
+        if processor.get_history_limit() == 0 {
+            return Err("execution history is not recorded".to_string());
+        }
+        let mut undone = 0u64;
+        while count.is_none_or(|count| undone < count) {
+            if !processor.step_back() {
+                println!("reached the start of recorded history (step {})", processor.get_step_count());
+                break;
+            }
+            undone += 1;
+            if self.breakpoints.contains(&processor.get_instruction_pointer()) {
+                println!("breakpoint hit at instruction {}", self.describe_location(processor.get_instruction_pointer()));
+                break;
+            }
+        }
+        self.show_location(processor);
+        Ok(())
+    }
+
+    fn report_watch_hits<T: Tape>(&self, processor: &Processor<T>, watch_hits: &[WatchHit]) {
+        for hit in watch_hits.iter() {
+            let watchpoint = match processor.get_watchpoints().get(hit.watchpoint) {
+                Some(watchpoint) => watchpoint.to_string(),
+                None => format!("[{}]", hit.address)
+            };
+            let change = match hit.access {
+                Access::Read => format!("read {}", hit.old_value),
+                Access::Write => format!("{} -> {}", hit.old_value, hit.new_value)
+            };
+            println!("watchpoint {} ({}) triggered by instruction {} ({}): {}",
+                hit.watchpoint, watchpoint, self.describe_location(hit.instruction_pointer), hit.instruction, change);
+        }
+    }
+
+    fn resolve_location(&self, arguments: &[&str]) -> Result<InstructionLocation, String> {
+        let argument = match arguments.first() {
+            Some(argument) => *argument,
+            None => return Err("no instruction index or label provided".to_string())
+        };
+        let location = match argument.parse::<InstructionLocation>() {
+            Ok(location) => location,
+            Err(_) => self.program.find_label(argument).ok_or(format!("unknown label {}", argument))?
+        };
+        if location >= self.program.instructions.len() {
+            return Err(format!("there is no instruction at {}", argument));
+        }
+        Ok(location)
+    }
+
+    // instruction index along with labels marking it, e.g. "3 (loop)"
+    fn describe_location(&self, location: InstructionLocation) -> String {
+        let labels: Vec<&str> = self.program.labels_at(location).collect();
+        if labels.is_empty() { return location.to_string(); }
+        format!("{} ({})", location, labels.join(", "))
+    }
+
+    // address along with symbols naming it, e.g. "[4] (SUM)"
+    fn describe_cell(&self, address: MemoryLocation) -> String {
+        let symbols: Vec<&str> = self.program.symbols_at(address).collect();
+        if symbols.is_empty() { return format!("[{}]", address); }
+        format!("[{}] ({})", address, symbols.join(", "))
+    }
+
+    fn parse_cell(&self, argument: Option<&&str>) -> Result<Cell, String> {
+        match argument {
+            Some(&"acc") => Ok(Cell::Accumulator),
+            Some(argument) => match argument.parse::<MemoryLocation>() {
+                Ok(address) => Ok(Cell::Address(address)),
+                Err(_) => match self.program.find_symbol(argument) {
+                    Some(symbol) if symbol.kind == SymbolKind::Address => symbol.value.to_address().map(Cell::Address)
+                        .ok_or(format!("symbol {} is not a valid address", argument)),
+                    Some(_) => Err(format!("symbol {} is a constant, not an address", argument)),
+                    None => Err(format!("incorrect address {}", argument))
+                }
+            },
+            None => Err("no address provided".to_string())
+        }
+    }
+
+    fn show_location<T: Tape>(&self, processor: &Processor<T>) {
+        let location = processor.get_instruction_pointer();
+        match self.program.source_line(location) {
+            Some(line) => println!("=> {} | line {}: {}", self.describe_location(location), line, self.source[line - 1].trim()),
+            None => println!("=> {} | no instruction (end of program)", location)
+        }
+    }
+
+    fn list<T: Tape>(&self, processor: &Processor<T>) {
+        let current_line = self.program.source_line(processor.get_instruction_pointer());
+        let center = current_line.unwrap_or(self.source.len());
+        let first = center.saturating_sub(5).max(1);
+        let last = (center + 5).min(self.source.len());
+        for line in first..=last {
+            let marker = if Some(line) == current_line { "=>" } else { "  " };
+            println!("{} {:4} | {}", marker, line, self.source[line - 1]);
+        }
+    }
+}
+
+fn parse_comparison(argument: &str) -> Result<Comparison, String> {
+    match argument {
+        "<" => Ok(Comparison::Less),
+        "<=" => Ok(Comparison::LessOrEqual),
+        "==" => Ok(Comparison::Equal),
+        "!=" => Ok(Comparison::NotEqual),
+        ">" => Ok(Comparison::Greater),
+        ">=" => Ok(Comparison::GreaterOrEqual),
+        _ => Err(format!("unknown comparison {}", argument))
+    }
+}
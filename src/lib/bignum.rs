@@ -0,0 +1,392 @@
+use std::{cmp::Ordering, fmt, ops::{Add, Div, Mul, Neg, Sub}, str::FromStr};
+
+// arbitrary-precision signed integer, stored as sign and magnitude
+// magnitude is kept in little-endian base 2^32 limbs without trailing zero limbs,
+// so zero is represented by an empty magnitude (and is never negative)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseBigIntError;
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid digit found in string")
+    }
+}
+
+impl std::error::Error for ParseBigIntError {}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        BigInt { negative: false, magnitude: Vec::new() }
+    }
+
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        let negative = negative && !magnitude.is_empty();
+        BigInt { negative, magnitude }
+    }
+
+    fn from_u64(value: u64) -> Self {
+        BigInt::from_parts(false, vec![value as u32, (value >> 32) as u32])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_positive(&self) -> bool {
+        !self.negative && !self.magnitude.is_empty()
+    }
+
+    // number of bits needed to represent absolute value (0 for zero)
+    pub fn bit_length(&self) -> u64 {
+        match self.magnitude.last() {
+            Some(top) => (self.magnitude.len() as u64 - 1) * 32 + (32 - top.leading_zeros() as u64),
+            None => 0
+        }
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.negative || self.magnitude.len() > 2 { return None; }
+        Some(self.magnitude.iter().rev().fold(0u64, |acc, limb| (acc << 32) | *limb as u64))
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 { return None; }
+        let absolute = self.magnitude.iter().rev().fold(0u64, |acc, limb| (acc << 32) | *limb as u64);
+        if self.negative {
+            if absolute > i64::MAX as u64 + 1 { return None; }
+            Some((absolute as i64).wrapping_neg())
+        } else {
+            i64::try_from(absolute).ok()
+        }
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from_parts(false, self.magnitude.clone())
+    }
+
+    fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+        if a.len() != b.len() { return a.len().cmp(&b.len()); }
+        for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+            if x != y { return x.cmp(y); }
+        }
+        Ordering::Equal
+    }
+
+    fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut result = Vec::<u32>::with_capacity(a.len().max(b.len()) + 1);
+        let mut carry = 0u64;
+        for index in 0..a.len().max(b.len()) {
+            let sum = *a.get(index).unwrap_or(&0) as u64 + *b.get(index).unwrap_or(&0) as u64 + carry;
+            result.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry != 0 { result.push(carry as u32); }
+        result
+    }
+
+    // requires |a| >= |b|
+    fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut result = Vec::<u32>::with_capacity(a.len());
+        let mut borrow = 0i64;
+        for (index, limb) in a.iter().enumerate() {
+            let mut difference = *limb as i64 - *b.get(index).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += 1 << 32;
+                borrow = 1;
+            }
+            result.push(difference as u32);
+        }
+        result
+    }
+
+    fn mul_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+        if a.is_empty() || b.is_empty() { return Vec::new(); }
+        let mut result = vec![0u32; a.len() + b.len()];
+        for (i, x) in a.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, y) in b.iter().enumerate() {
+                let product = *x as u64 * *y as u64 + result[i + j] as u64 + carry;
+                result[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            result[i + b.len()] = carry as u32;
+        }
+        result
+    }
+
+    // divides magnitude by a single limb in place, returns remainder
+    fn divide_magnitude_by_limb(magnitude: &mut [u32], divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in magnitude.iter_mut().rev() {
+            let current = (remainder << 32) | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        remainder as u32
+    }
+
+    // binary long division, returns (quotient, remainder); requires non-empty divisor
+    fn divide_magnitudes(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+        if b.len() == 1 {
+            let mut quotient = a.to_vec();
+            let remainder = BigInt::divide_magnitude_by_limb(&mut quotient, b[0]);
+            return (quotient, vec![remainder]);
+        }
+        let mut quotient = vec![0u32; a.len()];
+        let mut remainder = Vec::<u32>::new();
+        for bit in (0..a.len() * 32).rev() {
+            // remainder = remainder * 2 + current bit of a
+            let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+            for limb in remainder.iter_mut() {
+                let shifted_out = *limb >> 31;
+                *limb = (*limb << 1) | carry;
+                carry = shifted_out;
+            }
+            if carry != 0 { remainder.push(carry); }
+            if BigInt::compare_magnitudes(&remainder, b) != Ordering::Less {
+                remainder = BigInt::sub_magnitudes(&remainder, b);
+                while remainder.last() == Some(&0) { remainder.pop(); }
+                quotient[bit / 32] |= 1 << (bit % 32);
+            }
+        }
+        (quotient, remainder)
+    }
+
+    // division truncating towards zero (the same as i64 division), None if divisor is zero
+    pub fn checked_div(&self, divisor: &BigInt) -> Option<BigInt> {
+        if divisor.is_zero() { return None; }
+        let (quotient, _) = BigInt::divide_magnitudes(&self.magnitude, &divisor.magnitude);
+        Some(BigInt::from_parts(self.negative != divisor.negative, quotient))
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let mut result = BigInt::from_u64(value.unsigned_abs());
+        result.negative = value < 0;
+        result
+    }
+}
+
+impl From<usize> for BigInt {
+    fn from(value: usize) -> Self {
+        BigInt::from_u64(value as u64)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => BigInt::compare_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => BigInt::compare_magnitudes(&other.magnitude, &self.magnitude)
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, BigInt::add_magnitudes(&self.magnitude, &other.magnitude));
+        }
+        match BigInt::compare_magnitudes(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(other.negative, BigInt::sub_magnitudes(&other.magnitude, &self.magnitude)),
+            _ => BigInt::from_parts(self.negative, BigInt::sub_magnitudes(&self.magnitude, &other.magnitude))
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &(-other)
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(self.negative != other.negative, BigInt::mul_magnitudes(&self.magnitude, &other.magnitude))
+    }
+}
+
+impl Div for &BigInt {
+    type Output = BigInt;
+
+    // panics on division by zero, like primitive integers do
+    fn div(self, other: &BigInt) -> BigInt {
+        match self.checked_div(other) {
+            Some(result) => result,
+            None => panic!("attempt to divide by zero")
+        }
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match string.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, string.strip_prefix('+').unwrap_or(string))
+        };
+        if digits.is_empty() { return Err(ParseBigIntError); }
+        let mut magnitude = Vec::<u32>::new();
+        for digit in digits.chars() {
+            let digit = digit.to_digit(10).ok_or(ParseBigIntError)?;
+            // magnitude = magnitude * 10 + digit
+            let mut carry = digit as u64;
+            for limb in magnitude.iter_mut() {
+                let current = *limb as u64 * 10 + carry;
+                *limb = current as u32;
+                carry = current >> 32;
+            }
+            if carry != 0 { magnitude.push(carry as u32); }
+        }
+        Ok(BigInt::from_parts(negative, magnitude))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() { return f.pad_integral(true, "", "0"); }
+        // extract base 10^9 chunks, least significant first
+        let mut magnitude = self.magnitude.clone();
+        let mut chunks = Vec::<u32>::new();
+        while !magnitude.is_empty() {
+            chunks.push(BigInt::divide_magnitude_by_limb(&mut magnitude, 1_000_000_000));
+            while magnitude.last() == Some(&0) { magnitude.pop(); }
+        }
+        let mut digits = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            digits += &format!("{:09}", chunk);
+        }
+        f.pad_integral(!self.negative, "", &digits)
+    }
+}
+
+impl fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut digits = match self.magnitude.last() {
+            Some(top) => format!("{:x}", top),
+            None => "0".to_string()
+        };
+        for limb in self.magnitude.iter().rev().skip(1) {
+            digits += &format!("{:08x}", limb);
+        }
+        f.pad_integral(!self.negative, "0x", &digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+
+    fn big(text: &str) -> BigInt {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn add_carries_across_limbs() {
+        assert_eq!(&big("4294967295") + &big("1"), big("4294967296"));
+        assert_eq!(&big("18446744073709551615") + &big("1"), big("18446744073709551616"));
+        assert_eq!(&big("-18446744073709551615") + &big("-1"), big("-18446744073709551616"));
+        assert_eq!(&big("18446744073709551616") - &big("1"), big("18446744073709551615"));
+    }
+
+    #[test]
+    fn sub_crosses_zero() {
+        assert_eq!(&big("5") - &big("7"), big("-2"));
+        assert_eq!(&big("18446744073709551616") - &big("18446744073709551617"), big("-1"));
+        assert_eq!(&big("-3") - &big("-5"), big("2"));
+        assert_eq!(&big("7") - &big("7"), BigInt::zero());
+        assert!(!(&big("-7") - &big("-7")).is_negative());
+    }
+
+    #[test]
+    fn mul_multi_limb() {
+        assert_eq!(&big("18446744073709551615") * &big("18446744073709551615"), big("340282366920938463426481119284349108225"));
+        assert_eq!(&big("-4294967296") * &big("4294967296"), big("-18446744073709551616"));
+        let factorial = (1..=25).fold(big("1"), |acc, x| &acc * &BigInt::from(x as i64));
+        assert_eq!(factorial, big("15511210043330985984000000"));
+    }
+
+    #[test]
+    fn checked_div_truncates_towards_zero() {
+        assert_eq!(big("100").checked_div(&big("7")), Some(big("14")));
+        assert_eq!(big("-100").checked_div(&big("7")), Some(big("-14")));
+        assert_eq!(big("100").checked_div(&big("-7")), Some(big("-14")));
+        assert_eq!(big("-100").checked_div(&big("-7")), Some(big("14")));
+        assert_eq!(big("6").checked_div(&big("7")), Some(BigInt::zero()));
+        assert_eq!(big("1").checked_div(&BigInt::zero()), None);
+    }
+
+    #[test]
+    fn checked_div_multi_limb() {
+        assert_eq!(big("79228162514264337593543950336").checked_div(&big("1099511627779")), Some(big("72057594037731328")));
+        assert_eq!(big("-79228162514264337593543950336").checked_div(&big("1099511627779")), Some(big("-72057594037731328")));
+        assert_eq!(big("123456789012345678901234567890").checked_div(&big("9876543210987654321")), Some(big("12499999886")));
+        assert_eq!(big("123456789012345678901234567890").checked_div(&big("-123456789012345678901234567891")), Some(BigInt::zero()));
+    }
+
+    #[test]
+    fn display_and_parse_round_trip() {
+        for text in ["0", "1", "-1", "999999999", "1000000000", "-1000000001", "18446744073709551616",
+                     "-123456789012345678901234567890", "100000000000000000000000000000000000"] {
+            assert_eq!(big(text).to_string(), text);
+        }
+        assert_eq!(big("+42").to_string(), "42");
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("000123").to_string(), "123");
+        assert!("".parse::<BigInt>().is_err());
+        assert!("-".parse::<BigInt>().is_err());
+        assert!("12a".parse::<BigInt>().is_err());
+    }
+
+    #[test]
+    fn conversions_at_limits() {
+        assert_eq!(big("9223372036854775807").to_i64(), Some(i64::MAX));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775808").to_i64(), Some(i64::MIN));
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+        assert_eq!(big("18446744073709551615").to_u64(), Some(u64::MAX));
+        assert_eq!(big("18446744073709551616").to_u64(), None);
+        assert_eq!(big("9223372036854775808").to_address(), usize::try_from(1u64 << 63).ok());
+        assert_eq!(big("18446744073709551616").to_address(), None);
+        assert_eq!(big("-1").to_address(), None);
+    }
+}
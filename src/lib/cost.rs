@@ -0,0 +1,110 @@
+use crate::instructions::{Instruction, MemoryLocation, Operand};
+use crate::memory::Memory;
+use crate::value::Value;
+use std::collections::HashMap;
+
+// cost of executed program under uniform (every instruction/cell costs 1) and logarithmic
+// (cost proportional to bit length of operands and addresses) cost criteria
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cost {
+    pub uniform_time: u64,
+    pub logarithmic_time: u64,
+    pub uniform_space: u64,     // number of cells up to the highest address touched
+    pub logarithmic_space: u64  // sum of sizes of the biggest values held by every touched cell
+}
+
+// l(x) from logarithmic cost criterion - number of bits needed to represent x, at least 1
+pub fn logarithmic_size(value: &impl Value) -> u64 {
+    value.bit_length().max(1)
+}
+
+pub fn address_size(address: MemoryLocation) -> u64 {
+    ((usize::BITS - address.leading_zeros()) as u64).max(1)
+}
+
+// logarithmic cost of instruction executed on provided memory contents, following the
+// definitions from Aho, Hopcroft, Ullman "The Design and Analysis of Computer Algorithms",
+// where c(i) is contents of i-th cell and t(a) is cost of operand a:
+//   t(=i) = l(i), t(i) = l(i) + l(c(i)), t(^i) = l(i) + l(c(i)) + l(c(c(i)))
+// cost of input value read by READ is not included, as it is not known before executing it
+pub fn instruction_cost(instruction: &Instruction, memory: &Memory) -> u64 {
+    let cell = |address: MemoryLocation| memory.get(address);
+    let pointed_cell = |address: MemoryLocation| cell(address).to_address().map(cell).unwrap_or_default();
+    let accumulator = logarithmic_size(&cell(0));
+    let operand_cost = |operand: &Operand| match operand {
+        Operand::Immediate(value) => logarithmic_size(value),
+        Operand::ImmediateAddress(address) => address_size(*address) + logarithmic_size(&cell(*address)),
+        Operand::IntermediateAddress(address) => {
+            address_size(*address) + logarithmic_size(&cell(*address)) + logarithmic_size(&pointed_cell(*address))
+        },
+        Operand::Label(_) => 1
+    };
+    // cost of addressing the destination cell (STORE, READ)
+    let destination_cost = |operand: &Operand| match operand {
+        Operand::ImmediateAddress(address) => address_size(*address),
+        Operand::IntermediateAddress(address) => address_size(*address) + logarithmic_size(&cell(*address)),
+        _ => 1
+    };
+    match instruction {
+        Instruction::Load(operand) | Instruction::Write(operand, _) => operand_cost(operand),
+        Instruction::Add(operand) | Instruction::Sub(operand) |
+        Instruction::Mult(operand) | Instruction::Div(operand) => accumulator + operand_cost(operand),
+        Instruction::Store(operand) => accumulator + destination_cost(operand),
+        Instruction::Read(operand, _) => destination_cost(operand),
+        Instruction::Jgtz(_) | Instruction::Jzero(_) => accumulator,
+        Instruction::Jump(_) | Instruction::Jeof(_) | Instruction::Halt => 1
+    }
+}
+
+pub(crate) struct CostCounter {
+    cost: Cost,
+    cell_sizes: HashMap<MemoryLocation, u64> // the biggest size of value held by every touched cell
+}
+
+impl CostCounter {
+    pub fn new() -> Self {
+        CostCounter {
+            cost: Cost::default(),
+            cell_sizes: HashMap::new()
+        }
+    }
+
+    pub fn count_instruction(&mut self, logarithmic_cost: u64) {
+        self.cost.uniform_time += 1;
+        self.cost.logarithmic_time += logarithmic_cost;
+    }
+
+    // adds l(input) to the cost of READ instruction
+    pub fn count_input(&mut self, value: &impl Value) {
+        self.cost.logarithmic_time += logarithmic_size(value);
+    }
+
+    // records access (read or write) of the cell holding provided value,
+    // returns previous size of the cell if it grew (0 if the cell was not touched before)
+    pub fn count_access(&mut self, address: MemoryLocation, value: &impl Value) -> Option<u64> {
+        self.cost.uniform_space = self.cost.uniform_space.max((address as u64).saturating_add(1));
+        let size = logarithmic_size(value);
+        let cell_size = self.cell_sizes.entry(address).or_insert(0);
+        if size <= *cell_size { return None; }
+        let previous_size = *cell_size;
+        self.cost.logarithmic_space += size - previous_size;
+        *cell_size = size;
+        Some(previous_size)
+    }
+
+    // reverts counter to the state before accesses which grew provided cells (in order of accesses)
+    pub fn restore(&mut self, cost: Cost, cell_sizes: &[(MemoryLocation, u64)]) {
+        self.cost = cost;
+        for (address, size) in cell_sizes.iter().rev() {
+            if *size == 0 {
+                self.cell_sizes.remove(address);
+            } else {
+                self.cell_sizes.insert(*address, *size);
+            }
+        }
+    }
+
+    pub fn get(&self) -> Cost {
+        self.cost.clone()
+    }
+}
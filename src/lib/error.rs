@@ -0,0 +1,281 @@
+use crate::instructions::{ImmediateValue, Instruction, InstructionLocation, MemoryLocation, TapeNumber};
+use std::{error::Error, fmt, ops::Range};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    FileOpenFailed,
+    ReadFailed,
+    TooManyTokens,
+    MissingArgument,
+    UnknownInstruction,
+    IllegalLabelArgument,
+    IllegalImmediateArgument,
+    IllegalNonLabelArgument,
+    MalformedImmediateValue,
+    MalformedIntermediateAddress,
+    MalformedLabelDefinition,
+    MalformedTapeNumber,
+    IllegalTapeSelector,
+    EmptyLabel,
+    RedefinedLabel,
+    UndefinedLabel,
+    UnknownDirective,
+    MalformedSymbolDefinition,
+    RedefinedSymbol,
+    UndefinedSymbol,
+    IllegalAddress,
+    MalformedExpression,
+    ExpressionOverflow,
+    ExpressionDivisionByZero,
+    ConstantAsAddress,
+    MalformedDataDefinition,
+    RedefinedData
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub line: usize,         // 1-based, 0 if the error is not related to any line
+    pub span: Range<usize>,  // byte range of offending part of the line
+    pub token: String,
+    pub hint: Option<String>,
+    pub source_line: String  // contents of the offending line, empty if not available
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, line: usize, span: Range<usize>, token: &str) -> Self {
+        ParseError {
+            kind,
+            line,
+            span,
+            token: token.to_string(),
+            hint: None,
+            source_line: String::new()
+        }
+    }
+
+    pub fn with_hint(mut self, hint: String) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    // 1-based column (in characters) of the offending part of the line, 0 if not related to any line
+    pub fn column(&self) -> usize {
+        if self.line == 0 { return 0; }
+        match self.source_line.get(..self.span.start) {
+            Some(prefix) => prefix.chars().count() + 1,
+            None => self.span.start + 1
+        }
+    }
+
+    pub fn description(&self) -> String {
+        let token = &self.token;
+        match self.kind {
+            ParseErrorKind::FileOpenFailed => format!("could not open \"{}\" file to read", token),
+            ParseErrorKind::ReadFailed => "reading line failed".to_string(),
+            ParseErrorKind::TooManyTokens => format!("too many tokens in `{}`", token),
+            ParseErrorKind::MissingArgument => format!("no argument provided in `{}`", token),
+            ParseErrorKind::UnknownInstruction => format!("unknown instruction `{}`", token),
+            ParseErrorKind::IllegalLabelArgument => format!("illegal label argument `{}`", token),
+            ParseErrorKind::IllegalImmediateArgument => format!("illegal immediate argument `{}`", token),
+            ParseErrorKind::IllegalNonLabelArgument => format!("illegal non-label argument `{}`", token),
+            ParseErrorKind::MalformedImmediateValue => format!("malformed immediate value `{}`", token),
+            ParseErrorKind::MalformedIntermediateAddress => format!("malformed intermediate address value `{}`", token),
+            ParseErrorKind::MalformedLabelDefinition => format!("malformed label definition `{}`", token),
+            ParseErrorKind::MalformedTapeNumber => format!("malformed tape number `{}`", token),
+            ParseErrorKind::IllegalTapeSelector => format!("illegal tape selector `{}`", token),
+            ParseErrorKind::EmptyLabel => format!("empty label `{}`", token),
+            ParseErrorKind::RedefinedLabel => format!("redefined label `{}`", token),
+            ParseErrorKind::UndefinedLabel => format!("label `{}` not defined", token),
+            ParseErrorKind::UnknownDirective => format!("unknown directive `{}`", token),
+            ParseErrorKind::MalformedSymbolDefinition => format!("malformed symbol definition `{}`", token),
+            ParseErrorKind::RedefinedSymbol => format!("redefined symbol `{}`", token),
+            ParseErrorKind::UndefinedSymbol => format!("symbol `{}` not defined", token),
+            ParseErrorKind::IllegalAddress => format!("`{}` is not a valid address", token),
+            ParseErrorKind::MalformedExpression => format!("malformed expression `{}`", token),
+            ParseErrorKind::ExpressionOverflow => format!("value of `{}` does not fit in a memory cell", token),
+            ParseErrorKind::ExpressionDivisionByZero => format!("division by zero in `{}`", token),
+            ParseErrorKind::ConstantAsAddress => format!("constant `{}` used as an address", token),
+            ParseErrorKind::MalformedDataDefinition => format!("malformed data definition `{}`", token),
+            ParseErrorKind::RedefinedData => format!("memory cell initialized again with `{}`", token)
+        }
+    }
+
+    // renders the error in rustc-like fashion, with the offending line and its part underlined, e.g.
+    //   parser error: unknown instruction `jzer`
+    //    --> examples/test.rasm:7:5
+    //     |
+    //   7 |     jzer end
+    //     |     ^^^^
+    //     = help: did you mean `jzero`?
+    pub fn render(&self, origin: &str) -> String {
+        let mut lines = vec![format!("parser error: {}", self.description())];
+        let line_number = if self.line == 0 { String::new() } else { self.line.to_string() };
+        let gutter = " ".repeat(line_number.len());
+        if self.line == 0 {
+            lines.push(format!(" --> {}", origin));
+        } else {
+            lines.push(format!("{}--> {}:{}:{}", gutter, origin, self.line, self.column()));
+        }
+        if self.line != 0 && !self.source_line.is_empty() {
+            let start = self.span.start.min(self.source_line.len());
+            let end = self.span.end.clamp(start, self.source_line.len());
+            // keep tabs in padding so that carets line up with the source line
+            let padding: String = self.source_line[..start].chars().map(|x| if x == '\t' { '\t' } else { ' ' }).collect();
+            let underline = "^".repeat(self.source_line[start..end].chars().count().max(1));
+            lines.push(format!("{} |", gutter));
+            lines.push(format!("{} | {}", line_number, self.source_line));
+            lines.push(format!("{} | {}{}", gutter, padding, underline));
+        }
+        if let Some(hint) = &self.hint {
+            lines.push(format!("{} = help: {}", gutter, hint));
+        }
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 { return write!(f, "{}", self.description()); }
+        write!(f, "{} at line {}", self.description(), self.line)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TapeError {
+    EndOfTape,
+    Malformed(String), // text which is not a valid value
+    Io(String),        // description of the underlying I/O error
+    UnknownTape(TapeNumber)
+}
+
+impl fmt::Display for TapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TapeError::EndOfTape => write!(f, "tried to read past the end of input tape"),
+            TapeError::Malformed(text) => write!(f, "malformed value `{}` on input tape", text),
+            TapeError::Io(description) => write!(f, "reading input tape failed: {}", description),
+            TapeError::UnknownTape(tape) => write!(f, "there is no tape number {}", tape)
+        }
+    }
+}
+
+impl Error for TapeError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionErrorKind {
+    DivisionByZero,
+    ArithmeticOverflow(ImmediateValue, ImmediateValue), // (accumulator, operand)
+    NegativeIndirectPointer(MemoryLocation, ImmediateValue), // (pointer cell, value found in it)
+    Tape(TapeError),
+    InstructionPointerOutOfRange,
+    MemoryOutOfBounds(MemoryLocation),
+    PointerOutOfBounds(ImmediateValue), // value of intermediate pointer too big to be an address
+    IllegalOperand,
+    StepLimitExceeded(u64),
+    DataOutOfBounds(MemoryLocation, usize) // (address of initial data, memory size)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionError {
+    pub kind: ExecutionErrorKind,
+    pub instruction_pointer: InstructionLocation,
+    pub instruction: Option<Instruction> // None if instruction pointer does not point to any instruction
+}
+
+impl ExecutionError {
+    pub fn new(kind: ExecutionErrorKind, instruction_pointer: InstructionLocation, instruction: Option<Instruction>) -> Self {
+        ExecutionError {
+            kind,
+            instruction_pointer,
+            instruction
+        }
+    }
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExecutionErrorKind::DivisionByZero => write!(f, "division by zero"),
+            ExecutionErrorKind::ArithmeticOverflow(lhs, rhs) => match &self.instruction {
+                Some(instruction) => write!(f, "arithmetic overflow in {} operation on {} and {}", instruction.mnemonic(), lhs, rhs),
+                None => write!(f, "arithmetic overflow on {} and {}", lhs, rhs)
+            },
+            ExecutionErrorKind::NegativeIndirectPointer(_, _) => write!(f, "intermediate pointer must be non-negative"),
+            ExecutionErrorKind::Tape(error) => write!(f, "{}", error),
+            ExecutionErrorKind::InstructionPointerOutOfRange => write!(f, "instruction pointer run out of instruction space, processor halted"),
+            ExecutionErrorKind::MemoryOutOfBounds(address) => match &self.instruction {
+                Some(instruction) => write!(f, "{} operation accessed memory out of bounds at address {}", instruction.mnemonic(), address),
+                None => write!(f, "memory access out of bounds at address {}", address)
+            },
+            ExecutionErrorKind::PointerOutOfBounds(address) => match &self.instruction {
+                Some(instruction) => write!(f, "{} operation accessed memory out of bounds at address {}", instruction.mnemonic(), address),
+                None => write!(f, "memory access out of bounds at address {}", address)
+            },
+            ExecutionErrorKind::IllegalOperand => match self.instruction.as_ref().and_then(|x| x.operand().map(|operand| (x, operand))) {
+                Some((instruction, operand)) => write!(f, "{} operation cannot be provided with {}", instruction.mnemonic(), operand.kind_name()),
+                None => write!(f, "illegal operand")
+            },
+            ExecutionErrorKind::StepLimitExceeded(limit) => write!(f, "step limit of {} instructions exceeded", limit),
+            ExecutionErrorKind::DataOutOfBounds(address, size) => write!(f, "initial data at address {} does not fit in memory of size {}", address, size)
+        }
+    }
+}
+
+impl Error for ExecutionError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotErrorKind {
+    FileOpenFailed,
+    WriteFailed,
+    UnknownFormat,
+    UnexpectedEnd,
+    UnknownField,
+    MissingField,
+    MalformedValue,
+    MalformedInstruction,
+    CellOutOfBounds
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotError {
+    pub kind: SnapshotErrorKind,
+    pub line: usize, // 1-based, 0 if the error is not related to any line
+    pub token: String
+}
+
+impl SnapshotError {
+    pub fn new(kind: SnapshotErrorKind, line: usize, token: &str) -> Self {
+        SnapshotError {
+            kind,
+            line,
+            token: token.to_string()
+        }
+    }
+
+    pub fn description(&self) -> String {
+        let token = &self.token;
+        match self.kind {
+            SnapshotErrorKind::FileOpenFailed => format!("could not open \"{}\" file to read", token),
+            SnapshotErrorKind::WriteFailed => format!("could not write \"{}\" file", token),
+            SnapshotErrorKind::UnknownFormat => format!("not a snapshot or unsupported snapshot version `{}`", token),
+            SnapshotErrorKind::UnexpectedEnd => format!("unexpected end of snapshot in `{}` section", token),
+            SnapshotErrorKind::UnknownField => format!("unknown field `{}`", token),
+            SnapshotErrorKind::MissingField => format!("missing field `{}`", token),
+            SnapshotErrorKind::MalformedValue => format!("malformed value `{}`", token),
+            SnapshotErrorKind::MalformedInstruction => format!("malformed instruction `{}`", token),
+            SnapshotErrorKind::CellOutOfBounds => format!("cell `{}` is past the end of memory", token)
+        }
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 { return write!(f, "{}", self.description()); }
+        write!(f, "{} at line {}", self.description(), self.line)
+    }
+}
+
+impl Error for SnapshotError {}
@@ -0,0 +1,169 @@
+use crate::error::ParseErrorKind;
+use crate::instructions::ImmediateValue;
+use crate::processor::OverflowMode;
+use crate::value::{ArithmeticOperation, Value};
+use std::ops::Range;
+
+// error kind along with byte range of the offending part of the expression
+pub(crate) type ExpressionError = (ParseErrorKind, Range<usize>);
+
+// evaluates constant expression, e.g. `BASE+2*(N-1)`, built from integers, symbols (resolved by provided function),
+// binary `+`, `-`, `*`, `/` with usual precedence, unary `-` and parentheses; division truncates like DIV
+pub(crate) fn evaluate(text: &str, symbol: impl Fn(&str) -> Option<ImmediateValue>) -> Result<ImmediateValue, ExpressionError> {
+    let mut evaluator = Evaluator { text, position: 0, symbol };
+    let value = evaluator.sum()?;
+    if evaluator.position < text.len() {
+        return Err(evaluator.unexpected());
+    }
+    Ok(value)
+}
+
+struct Evaluator<'a, F: Fn(&str) -> Option<ImmediateValue>> {
+    text: &'a str,
+    position: usize, // byte offset of the next character
+    symbol: F
+}
+
+impl<F: Fn(&str) -> Option<ImmediateValue>> Evaluator<'_, F> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    // the next character (or the end of expression) is not what was expected
+    fn unexpected(&self) -> ExpressionError {
+        let end = self.peek().map_or(self.position, |x| self.position + x.len_utf8());
+        (ParseErrorKind::MalformedExpression, self.position..end)
+    }
+
+    fn sum(&mut self) -> Result<ImmediateValue, ExpressionError> {
+        let start = self.position;
+        let mut value = self.product()?;
+        while let Some(operator @ ('+' | '-')) = self.peek() {
+            self.position += 1;
+            let operation = if operator == '+' { ArithmeticOperation::Add } else { ArithmeticOperation::Sub };
+            let other = self.product()?;
+            value = calculate(operation, value, other, start..self.position)?;
+        }
+        Ok(value)
+    }
+
+    fn product(&mut self) -> Result<ImmediateValue, ExpressionError> {
+        let start = self.position;
+        let mut value = self.unary()?;
+        while let Some(operator @ ('*' | '/')) = self.peek() {
+            self.position += 1;
+            let operation = if operator == '*' { ArithmeticOperation::Mult } else { ArithmeticOperation::Div };
+            let other = self.unary()?;
+            value = calculate(operation, value, other, start..self.position)?;
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<ImmediateValue, ExpressionError> {
+        let start = self.position;
+        if self.peek() == Some('-') {
+            self.position += 1;
+            let value = self.unary()?;
+            return calculate(ArithmeticOperation::Sub, ImmediateValue::default(), value, start..self.position);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<ImmediateValue, ExpressionError> {
+        let start = self.position;
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let value = self.sum()?;
+                if self.peek() != Some(')') { return Err(self.unexpected()); }
+                self.position += 1;
+                Ok(value)
+            },
+            Some(character) if character.is_ascii_digit() => {
+                let length = self.text[start..].find(|x: char| !x.is_ascii_digit()).unwrap_or(self.text.len() - start);
+                self.position += length;
+                self.text[start..self.position].parse::<ImmediateValue>().map_err(|_| (ParseErrorKind::ExpressionOverflow, start..self.position))
+            },
+            Some(character) if character.is_alphabetic() || character == '_' => {
+                let length = self.text[start..].find(|x: char| !x.is_alphanumeric() && x != '_').unwrap_or(self.text.len() - start);
+                self.position += length;
+                (self.symbol)(&self.text[start..self.position]).ok_or((ParseErrorKind::UndefinedSymbol, start..self.position))
+            },
+            _ => Err(self.unexpected())
+        }
+    }
+}
+
+fn calculate(operation: ArithmeticOperation, lhs: ImmediateValue, rhs: ImmediateValue, span: Range<usize>) -> Result<ImmediateValue, ExpressionError> {
+    if operation == ArithmeticOperation::Div && rhs.is_zero() {
+        return Err((ParseErrorKind::ExpressionDivisionByZero, span));
+    }
+    lhs.calculate(operation, &rhs, OverflowMode::Trap).ok_or((ParseErrorKind::ExpressionOverflow, span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str) -> Result<ImmediateValue, ExpressionError> {
+        evaluate(text, |name| match name {
+            "N" => Some(ImmediateValue::from(10i64)),
+            "BASE" => Some(ImmediateValue::from(100i64)),
+            _ => None
+        })
+    }
+
+    fn value(x: i64) -> Result<ImmediateValue, ExpressionError> {
+        Ok(ImmediateValue::from(x))
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("BASE+2*N-1"), value(119));
+        assert_eq!(eval("2*3+4*5"), value(26));
+        assert_eq!(eval("20-6/3-1"), value(17));
+        assert_eq!(eval("100/10/5"), value(2));
+        assert_eq!(eval("-7/2"), value(-3));
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-N"), value(-10));
+        assert_eq!(eval("-(-N)"), value(10));
+        assert_eq!(eval("--3"), value(3));
+        assert_eq!(eval("2*-3"), value(-6));
+        assert_eq!(eval("-2*-3"), value(6));
+    }
+
+    #[test]
+    fn nested_parentheses() {
+        assert_eq!(eval("(1+2)*3"), value(9));
+        assert_eq!(eval("((N))"), value(10));
+        assert_eq!(eval("2*(BASE-(N-(3+1))*2)"), value(176));
+        assert_eq!(eval("-(2*(3-(4)))"), value(2));
+    }
+
+    #[test]
+    fn malformed_spans() {
+        assert_eq!(eval("(1+2"), Err((ParseErrorKind::MalformedExpression, 4..4)));
+        assert_eq!(eval("1+2)"), Err((ParseErrorKind::MalformedExpression, 3..4)));
+        assert_eq!(eval("2*"), Err((ParseErrorKind::MalformedExpression, 2..2)));
+        assert_eq!(eval("N+X"), Err((ParseErrorKind::UndefinedSymbol, 2..3)));
+    }
+
+    #[test]
+    fn division_by_zero_spans() {
+        assert_eq!(eval("1/0"), Err((ParseErrorKind::ExpressionDivisionByZero, 0..3)));
+        assert_eq!(eval("BASE+N/(N-10)"), Err((ParseErrorKind::ExpressionDivisionByZero, 5..13)));
+        assert_eq!(eval("N*2/0+1"), Err((ParseErrorKind::ExpressionDivisionByZero, 0..5)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn overflow_spans() {
+        assert_eq!(eval("9223372036854775807+1"), Err((ParseErrorKind::ExpressionOverflow, 0..21)));
+        assert_eq!(eval("1+9223372036854775807*2"), Err((ParseErrorKind::ExpressionOverflow, 2..23)));
+        assert_eq!(eval("N+9223372036854775808"), Err((ParseErrorKind::ExpressionOverflow, 2..21)));
+        assert_eq!(eval("-9223372036854775807-1"), value(i64::MIN));
+    }
+}
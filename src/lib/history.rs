@@ -0,0 +1,202 @@
+use crate::cost::Cost;
+use crate::instructions::{ImmediateValue, InstructionLocation, MemoryLocation, TapeNumber};
+use std::collections::VecDeque;
+
+// everything needed to undo a single executed instruction
+pub(crate) struct Step {
+    pub instruction_pointer: InstructionLocation,
+    pub halted: bool,
+    pub end_of_input: bool,
+    pub memory_size: usize,                                  // memory may be grown by MemoryPolicy::AutoGrow
+    pub writes: Vec<(MemoryLocation, ImmediateValue)>,       // overwritten cells and their previous values
+    pub input: Option<(TapeNumber, ImmediateValue)>,         // value consumed from input tape
+    pub output: Option<TapeNumber>,                          // tape a value was written to
+    pub cost: Cost,
+    pub cell_sizes: Vec<(MemoryLocation, u64)>               // previous sizes of cells grown in cost accounting
+}
+
+impl Step {
+    pub fn new(instruction_pointer: InstructionLocation, halted: bool, end_of_input: bool, memory_size: usize, cost: Cost) -> Self {
+        Step {
+            instruction_pointer,
+            halted,
+            end_of_input,
+            memory_size,
+            writes: Vec::new(),
+            input: None,
+            output: None,
+            cost,
+            cell_sizes: Vec::new()
+        }
+    }
+}
+
+// undo log of the most recently executed instructions, bounded to keep memory usage constant on long runs
+pub(crate) struct History {
+    limit: usize, // 0 disables recording
+    steps: VecDeque<Step>,
+    // tapes cannot be rewound, so values read by undone instructions are kept to be read again,
+    // and writes of undone instructions are not repeated on the output tape when executed again
+    pending_input: Vec<Vec<ImmediateValue>>, // for every tape, the next value to read is at the end
+    suppressed_output: Vec<u64>              // for every tape
+}
+
+// element for provided tape number, vectors indexed by tapes are extended on demand
+pub(crate) fn tape_entry<V: Default>(entries: &mut Vec<V>, tape: TapeNumber) -> &mut V {
+    if entries.len() <= tape {
+        entries.resize_with(tape + 1, V::default);
+    }
+    &mut entries[tape]
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            limit: 0,
+            steps: VecDeque::new(),
+            pending_input: Vec::new(),
+            suppressed_output: Vec::new()
+        }
+    }
+
+    // forgets recorded steps and tape contents kept for them, keeping the limit
+    pub fn clear(&mut self) {
+        self.steps.clear();
+        self.pending_input.clear();
+        self.suppressed_output.clear();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    pub fn get_limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.steps.len() > limit {
+            self.steps.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn push(&mut self, step: Step) {
+        if self.limit == 0 { return; }
+        if self.steps.len() == self.limit {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(step);
+    }
+
+    pub fn pop(&mut self) -> Option<Step> {
+        self.steps.pop_back()
+    }
+
+    pub fn unread(&mut self, tape: TapeNumber, value: ImmediateValue) {
+        tape_entry(&mut self.pending_input, tape).push(value);
+    }
+
+    pub fn take_pending_input(&mut self, tape: TapeNumber) -> Option<ImmediateValue> {
+        self.pending_input.get_mut(tape)?.pop()
+    }
+
+    pub fn unwrite(&mut self, tape: TapeNumber) {
+        *tape_entry(&mut self.suppressed_output, tape) += 1;
+    }
+
+    // returns true if the write repeats one already on the output tape
+    pub fn take_suppressed_output(&mut self, tape: TapeNumber) -> bool {
+        match self.suppressed_output.get_mut(tape) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            },
+            _ => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+    use crate::parser::parse_str;
+    use crate::processor::{MemoryPolicy, Processor};
+    use crate::tape::VecTape;
+
+    fn step(instruction_pointer: InstructionLocation) -> Step {
+        Step::new(instruction_pointer, false, false, 1, Cost::default())
+    }
+
+    #[test]
+    fn limit_drops_oldest_steps() {
+        let mut history = History::new();
+        history.push(step(0));
+        assert_eq!(history.len(), 0);
+        history.set_limit(2);
+        for instruction_pointer in 1..=3 {
+            history.push(step(instruction_pointer));
+        }
+        assert_eq!(history.len(), 2);
+        history.set_limit(1);
+        assert_eq!(history.pop().map(|x| x.instruction_pointer), Some(3));
+        assert!(history.pop().is_none());
+    }
+
+    #[test]
+    fn tapes_are_kept_separately() {
+        let mut history = History::new();
+        // steps are undone from the last one, so values are unread in reverse order
+        history.unread(1, ImmediateValue::from(30i64));
+        history.unread(1, ImmediateValue::from(20i64));
+        history.unwrite(2);
+        history.unwrite(2);
+        assert_eq!(history.take_pending_input(0), None);
+        assert_eq!(history.take_pending_input(1), Some(ImmediateValue::from(20i64)));
+        assert_eq!(history.take_pending_input(1), Some(ImmediateValue::from(30i64)));
+        assert_eq!(history.take_pending_input(1), None);
+        assert!(!history.take_suppressed_output(0));
+        assert!(history.take_suppressed_output(2));
+        history.clear();
+        assert!(!history.take_suppressed_output(2));
+    }
+
+    #[test]
+    fn rewinding_undoes_every_effect_of_instructions() {
+        let program = parse_str("read 1\nload 1\nmult =2\nstore 20\nwrite 20\nhalt\n").unwrap();
+        let mut processor = Processor::new(program.instructions, Memory::new(4), Vec::new(), VecTape::new(vec![ImmediateValue::from(21i64)])).unwrap();
+        processor.set_memory_policy(MemoryPolicy::AutoGrow(64));
+        processor.set_history_limit(100);
+        let initial_cost = processor.get_cost();
+        while !processor.is_halted() {
+            processor.execute_instruction().unwrap();
+        }
+        let final_cost = processor.get_cost();
+        assert_eq!(processor.get_memory_cell(20), ImmediateValue::from(42i64));
+        assert_eq!(processor.get_tape_positions(0), (1, 1));
+        assert!(processor.get_memory_size() > 20);
+
+        assert!(!processor.rewind_to(7));
+        assert!(processor.rewind_to(0));
+        assert!(!processor.is_halted());
+        assert_eq!(processor.get_instruction_pointer(), 0);
+        assert_eq!(processor.get_accumulator(), ImmediateValue::default());
+        assert_eq!(processor.get_memory_cell(1), ImmediateValue::default());
+        assert_eq!(processor.get_memory_size(), 4);
+        assert_eq!(processor.get_tape_positions(0), (0, 0));
+        assert_eq!(processor.get_cost(), initial_cost);
+
+        // the only input value was already read from the tape, so it has to come from the history
+        while !processor.is_halted() {
+            processor.execute_instruction().unwrap();
+        }
+        assert_eq!(processor.get_memory_cell(20), ImmediateValue::from(42i64));
+        assert_eq!(processor.get_tape_positions(0), (1, 1));
+        assert_eq!(processor.get_cost(), final_cost);
+    }
+}
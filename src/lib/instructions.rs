@@ -1,6 +1,13 @@
+use std::fmt;
+
+// type of values stored in memory cells, unbounded integers can be enabled with "bigint" feature
+#[cfg(not(feature = "bigint"))]
 pub type ImmediateValue = i64;
+#[cfg(feature = "bigint")]
+pub type ImmediateValue = crate::bignum::BigInt;
 pub type MemoryLocation = usize;
 pub type InstructionLocation = usize;
+pub type TapeNumber = usize;
 
 #[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
@@ -11,6 +18,17 @@ pub enum Operand {
     Label(InstructionLocation)
 }
 
+impl Operand {
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Operand::Immediate(_) => "immediate",
+            Operand::ImmediateAddress(_) => "immediate address",
+            Operand::IntermediateAddress(_) => "intermediate address",
+            Operand::Label(_) => "label"
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub enum Instruction {
@@ -20,10 +38,70 @@ pub enum Instruction {
     Sub(Operand),
     Mult(Operand),
     Div(Operand),
-    Read(Operand),
-    Write(Operand),
+    Read(Operand, TapeNumber),
+    Write(Operand, TapeNumber),
     Jump(Operand),
     Jgtz(Operand),
     Jzero(Operand),
+    Jeof(Operand),
     Halt
+}
+
+impl Instruction {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Load(_) => "load",
+            Instruction::Store(_) => "store",
+            Instruction::Add(_) => "add",
+            Instruction::Sub(_) => "sub",
+            Instruction::Mult(_) => "mult",
+            Instruction::Div(_) => "div",
+            Instruction::Read(_, _) => "read",
+            Instruction::Write(_, _) => "write",
+            Instruction::Jump(_) => "jump",
+            Instruction::Jgtz(_) => "jgtz",
+            Instruction::Jzero(_) => "jzero",
+            Instruction::Jeof(_) => "jeof",
+            Instruction::Halt => "halt"
+        }
+    }
+
+    // tape used by READ and WRITE
+    pub fn tape(&self) -> Option<TapeNumber> {
+        match self {
+            Instruction::Read(_, tape) | Instruction::Write(_, tape) => Some(*tape),
+            _ => None
+        }
+    }
+
+    pub fn operand(&self) -> Option<&Operand> {
+        match self {
+            Instruction::Load(operand) | Instruction::Store(operand) | Instruction::Add(operand) |
+            Instruction::Sub(operand) | Instruction::Mult(operand) | Instruction::Div(operand) |
+            Instruction::Read(operand, _) | Instruction::Write(operand, _) | Instruction::Jump(operand) |
+            Instruction::Jgtz(operand) | Instruction::Jzero(operand) | Instruction::Jeof(operand) => Some(operand),
+            Instruction::Halt => None
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Immediate(value) => write!(f, "={}", value),
+            Operand::ImmediateAddress(address) => write!(f, "{}", address),
+            Operand::IntermediateAddress(address) => write!(f, "^{}", address),
+            Operand::Label(location) => write!(f, "{}", location)
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.operand(), self.tape()) {
+            (Some(operand), Some(tape)) if tape != 0 => write!(f, "{} {} @{}", self.mnemonic(), operand, tape),
+            (Some(operand), _) => write!(f, "{} {}", self.mnemonic(), operand),
+            (None, _) => write!(f, "{}", self.mnemonic())
+        }
+    }
 }
\ No newline at end of file
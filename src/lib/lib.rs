@@ -2,4 +2,15 @@ pub mod memory;
 pub mod instructions;
 pub mod tape;
 pub mod processor;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod program;
+pub mod watchpoint;
+pub mod error;
+pub mod value;
+pub mod bignum;
+pub mod cost;
+pub mod snapshot;
+pub mod trace;
+pub mod profile;
+mod history;
+mod expression;
@@ -1,38 +1,117 @@
+use crate::instructions::{ImmediateValue, MemoryLocation};
+use std::collections::HashMap;
+
+enum Storage {
+    Dense(Vec<ImmediateValue>),                      // fixed size, fast
+    Sparse(HashMap<MemoryLocation, ImmediateValue>)  // unbounded, only non-zero cells are stored
+}
 
 pub struct Memory {
-    storage: Vec<i64>
+    storage: Storage
 }
 
 impl Memory {
     pub fn new(size: usize) -> Self {
-        let mut new_memory = Memory {
-            storage: Vec::<i64>::new()
-        };
-        new_memory.storage.resize(size, 0);
-        new_memory
+        let mut storage = Vec::<ImmediateValue>::new();
+        storage.resize(size, ImmediateValue::default());
+        Memory {
+            storage: Storage::Dense(storage)
+        }
     }
 
-    pub fn set(&mut self, value: i64, index: usize) {
-        if index >= self.storage.len() { return }
-        self.storage[index] = value;
+    // memory modelling infinite register file of theoretical RAM machine
+    pub fn new_sparse() -> Self {
+        Memory {
+            storage: Storage::Sparse(HashMap::new())
+        }
     }
 
-    pub fn get(&self, index: usize) -> i64 {
-        if index >= self.storage.len() { return 0; }
-        self.storage[index]
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.storage, Storage::Sparse(_))
+    }
+
+    // number of addressable cells (usize::MAX for sparse memory)
+    pub fn size(&self) -> usize {
+        match &self.storage {
+            Storage::Dense(storage) => storage.len(),
+            Storage::Sparse(_) => usize::MAX
+        }
+    }
+
+    // whether the cell exists, every address is valid in sparse memory
+    pub fn contains(&self, address: MemoryLocation) -> bool {
+        match &self.storage {
+            Storage::Dense(storage) => address < storage.len(),
+            Storage::Sparse(_) => true
+        }
+    }
+
+    pub fn resize(&mut self, size: usize) {
+        if let Storage::Dense(storage) = &mut self.storage {
+            storage.resize(size, ImmediateValue::default());
+        }
+    }
+
+    pub fn set(&mut self, value: ImmediateValue, index: usize) {
+        match &mut self.storage {
+            Storage::Dense(storage) => {
+                if index >= storage.len() { return }
+                storage[index] = value;
+            },
+            Storage::Sparse(storage) => {
+                if value == ImmediateValue::default() {
+                    storage.remove(&index);
+                } else {
+                    storage.insert(index, value);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, index: usize) -> ImmediateValue {
+        match &self.storage {
+            Storage::Dense(storage) => storage.get(index).cloned().unwrap_or_default(),
+            Storage::Sparse(storage) => storage.get(&index).cloned().unwrap_or_default()
+        }
+    }
+
+    // non-zero cells in ascending order of addresses
+    pub fn cells(&self) -> Vec<(MemoryLocation, ImmediateValue)> {
+        match &self.storage {
+            Storage::Dense(storage) => storage.iter().enumerate()
+                .filter(|x| *x.1 != ImmediateValue::default())
+                .map(|(address, value)| (address, ImmediateValue::clone(value)))
+                .collect(),
+            Storage::Sparse(storage) => {
+                let mut cells: Vec<(MemoryLocation, ImmediateValue)> = storage.iter().map(|(address, value)| (*address, ImmediateValue::clone(value))).collect();
+                cells.sort_by_key(|x| x.0);
+                cells
+            }
+        }
     }
 
     #[allow(dead_code)]
     pub fn dump(&self) {
         print!("dumping memory: ");
-        for (index, value) in self.storage.iter().enumerate() {
-            if index % 8 == 0 {
-                println!();
-                print!("{:016x}: ", index);
+        match &self.storage {
+            Storage::Dense(storage) => {
+                for (index, value) in storage.iter().enumerate() {
+                    if index % 8 == 0 {
+                        println!();
+                        print!("{:016x}: ", index);
+                    }
+                    print!("{:016x} ", value);
+                }
+            },
+            Storage::Sparse(storage) => {
+                let mut addresses: Vec<&MemoryLocation> = storage.keys().collect();
+                addresses.sort();
+                for address in addresses {
+                    println!();
+                    print!("{:016x}: {:016x}", address, storage[address]);
+                }
             }
-            print!("{:016x} ", *value);
         }
     }
 
 }
-
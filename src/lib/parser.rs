@@ -1,205 +1,527 @@
-use crate::instructions::{Instruction, Operand};
-use std::{fs::{File}, io::{BufReader, BufRead}};
+use crate::instructions::{ImmediateValue, Instruction, MemoryLocation, Operand, TapeNumber};
+use crate::error::{ParseError, ParseErrorKind};
+use crate::expression;
+use crate::program::{Program, Symbol, SymbolKind};
+use crate::value::Value;
+use std::{collections::HashMap, fs::{File}, io::{BufReader, BufRead}, ops::Range};
 
 #[derive(PartialEq)]
 enum Entry {
     Label(String),
     Instruction(Instruction),
-    UnfinishedInstruction(Instruction, String) // only applicable to jumps (JUMP, JGTZ, JZERO)
+    UnfinishedInstruction(Instruction, Token), // only applicable to jumps (JUMP, JGTZ, JZERO)
+    UnresolvedName(String, Token)              // name in operand of other instruction, reported in finalize as a misused label or undefined symbol
+}
+
+const DIRECTIVES: [&str; 3] = [".equ", ".const", ".data"];
+
+const MNEMONICS: [&str; 13] = ["load", "store", "add", "sub", "mult", "div", "read", "write", "jump", "jgtz", "jzero", "jeof", "halt"];
+
+#[derive(PartialEq, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+    span: Range<usize> // byte range within the line
+}
+
+impl Token {
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(kind, self.line, self.span.clone(), &self.text)
+    }
 }
 
 struct State {
-    entries: Vec<Entry>    
+    entries: Vec<Entry>,
+    entry_lines: Vec<usize>, // source line of every entry
+    symbols: Vec<Symbol>,    // symbols defined so far, they have to be defined before use
+    data: HashMap<MemoryLocation, (ImmediateValue, usize)> // initial values of memory cells, with source line defining them
 }
 
 impl State {
     fn new() -> Self {
-        State { 
-            entries: Vec::<Entry>::new()  
+        State {
+            entries: Vec::<Entry>::new(),
+            entry_lines: Vec::<usize>::new(),
+            symbols: Vec::<Symbol>::new(),
+            data: HashMap::new()
         }
     }
 
-    fn parse_instruction(&self, line: Vec<String>, original_line: &str, line_number: usize) -> Result<Entry, String> {
-        if line.len() > 2 { return Err(format!("too many tokens in \"{}\" at line {}", original_line, line_number)); }
-
-        let argument;
-        if line.len() == 2 { 
-            let result = parse_argument(line[1].clone(), line_number);
-            match result {
-                Ok(operand) => argument = Some(operand),
-                Err(message) => return Err(message)
-            }
-        } else { argument = None };
-
-        let instruction_string = line[0].to_lowercase();
+    fn parse_instruction(&self, mut line: Vec<Token>, code: &str) -> Result<Entry, ParseError> {
+        let instruction_string = line[0].text.to_lowercase();
         let instruction = instruction_string.as_str();
+        // tape selector following the operand, e.g. `read 3 @1`
+        let selector = if line.len() == 3 && line[2].text.starts_with('@') { line.pop() } else { None };
+        if line.len() > 2 {
+            return Err(ParseError::new(ParseErrorKind::TooManyTokens, line[0].line, line[2].span.start..line[line.len() - 1].span.end, code)
+                .with_hint("instructions take at most one operand, comments have to start with `;`".to_string()));
+        }
+        if !MNEMONICS.contains(&instruction) {
+            let error = line[0].error(ParseErrorKind::UnknownInstruction);
+            return Err(match closest_match(instruction, MNEMONICS.iter().copied()) {
+                Some(candidate) => error.with_hint(format!("did you mean `{}`?", candidate)),
+                None => error
+            });
+        }
+
+        let tape = match selector {
+            Some(selector) => parse_tape_selector(instruction, &selector)?,
+            None => 0
+        };
+        let argument = if line.len() == 2 { Some(self.parse_argument(&line[1], instruction)?) } else { None };
         if instruction != "halt" && argument.is_none() {
-            return Err(format!("no argument provided in \"{}\" at line {}", original_line, line_number));
+            return Err(ParseError::new(ParseErrorKind::MissingArgument, line[0].line, line[0].span.clone(), code)
+                .with_hint(format!("`{}` expects an operand", instruction)));
         }
 
         match instruction {
             "load" => {
                 let argument = argument.unwrap();
-                if let Operand::Label(_) = argument { return Err(format!("illegal label argument provided in \"{}\" at line {}", original_line, line_number)); }
-                return Ok(Entry::Instruction(Instruction::Load(argument)));
+                if let Operand::Label(_) = argument { return label_argument(instruction, &line[1]); }
+                Ok(Entry::Instruction(Instruction::Load(argument)))
             },
             "store" => {
                 let argument = argument.unwrap();
-                if let Operand::Label(_) = argument { return Err(format!("illegal label argument provided in \"{}\" at line {}", original_line, line_number)); }
-                if let Operand::Immediate(_) = argument { return Err(format!("illegal immediate argument provided in \"{}\" at line {}", original_line, line_number)); }
-                return Ok(Entry::Instruction(Instruction::Store(argument)));
+                if let Operand::Label(_) = argument { return label_argument(instruction, &line[1]); }
+                if let Operand::Immediate(_) = argument { return Err(illegal_immediate_error(instruction, &line[1])); }
+                Ok(Entry::Instruction(Instruction::Store(argument)))
             },
             "add" => {
                 let argument = argument.unwrap();
-                if let Operand::Label(_) = argument { return Err(format!("illegal label argument provided in \"{}\" at line {}", original_line, line_number)); }
-                return Ok(Entry::Instruction(Instruction::Add(argument)));
+                if let Operand::Label(_) = argument { return label_argument(instruction, &line[1]); }
+                Ok(Entry::Instruction(Instruction::Add(argument)))
             },
             "sub" => {
                 let argument = argument.unwrap();
-                if let Operand::Label(_) = argument { return Err(format!("illegal label argument provided in \"{}\" at line {}", original_line, line_number)); }
-                return Ok(Entry::Instruction(Instruction::Sub(argument)));
+                if let Operand::Label(_) = argument { return label_argument(instruction, &line[1]); }
+                Ok(Entry::Instruction(Instruction::Sub(argument)))
             },
             "mult" => {
                 let argument = argument.unwrap();
-                if let Operand::Label(_) = argument { return Err(format!("illegal label argument provided in \"{}\" at line {}", original_line, line_number)); }
-                return Ok(Entry::Instruction(Instruction::Mult(argument)));
+                if let Operand::Label(_) = argument { return label_argument(instruction, &line[1]); }
+                Ok(Entry::Instruction(Instruction::Mult(argument)))
             },
             "div" => {
                 let argument = argument.unwrap();
-                if let Operand::Label(_) = argument { return Err(format!("illegal label argument provided in \"{}\" at line {}", original_line, line_number)); }
-                return Ok(Entry::Instruction(Instruction::Div(argument)));
+                if let Operand::Label(_) = argument { return label_argument(instruction, &line[1]); }
+                Ok(Entry::Instruction(Instruction::Div(argument)))
             },
             "read" => {
                 let argument = argument.unwrap();
-                if let Operand::Label(_) = argument { return Err(format!("illegal label argument provided in \"{}\" at line {}", original_line, line_number)); }
-                if let Operand::Immediate(_) = argument { return Err(format!("illegal immediate argument provided in \"{}\" at line {}", original_line, line_number)); }
-                return Ok(Entry::Instruction(Instruction::Read(argument)));
+                if let Operand::Label(_) = argument { return label_argument(instruction, &line[1]); }
+                if let Operand::Immediate(_) = argument { return Err(illegal_immediate_error(instruction, &line[1])); }
+                Ok(Entry::Instruction(Instruction::Read(argument, tape)))
             },
             "write" => {
                 let argument = argument.unwrap();
-                if let Operand::Label(_) = argument { return Err(format!("illegal label argument provided in \"{}\" at line {}", original_line, line_number)); }
-                return Ok(Entry::Instruction(Instruction::Write(argument)));
+                if let Operand::Label(_) = argument { return label_argument(instruction, &line[1]); }
+                Ok(Entry::Instruction(Instruction::Write(argument, tape)))
             },
             "jump" => {
                 let argument = argument.unwrap();
                 if let Operand::Label(_) = argument {
                     return Ok(Entry::UnfinishedInstruction(Instruction::Jump(argument), line[1].clone()));
                 }
-                return Err(format!("illegal non-label argument provided in \"{}\" at line {}", original_line, line_number));
+                Err(illegal_non_label_error(instruction, &line[1]))
             },
             "jgtz" => {
                 let argument = argument.unwrap();
                 if let Operand::Label(_) = argument {
                     return Ok(Entry::UnfinishedInstruction(Instruction::Jgtz(argument), line[1].clone()));
                 }
-                return Err(format!("illegal non-label argument provided in \"{}\" at line {}", original_line, line_number));
+                Err(illegal_non_label_error(instruction, &line[1]))
             },
             "jzero" => {
                 let argument = argument.unwrap();
                 if let Operand::Label(_) = argument {
                     return Ok(Entry::UnfinishedInstruction(Instruction::Jzero(argument), line[1].clone()));
                 }
-                return Err(format!("illegal non-label argument provided in \"{}\" at line {}", original_line, line_number));
+                Err(illegal_non_label_error(instruction, &line[1]))
+            },
+            "jeof" => {
+                let argument = argument.unwrap();
+                if let Operand::Label(_) = argument {
+                    return Ok(Entry::UnfinishedInstruction(Instruction::Jeof(argument), line[1].clone()));
+                }
+                Err(illegal_non_label_error(instruction, &line[1]))
             },
             "halt" => {
                 Ok(Entry::Instruction(Instruction::Halt))
             },
-            _ => return Err(format!("unknown instruction {} at line {}", instruction, line_number))
+            _ => Err(line[0].error(ParseErrorKind::UnknownInstruction))
+        }
+    }
+
+    // .equ NAME value, .const NAME =value, .data address: value, value, ...
+    fn parse_directive(&mut self, line: Vec<Token>, code: &str) -> Result<(), ParseError> {
+        let directive = line[0].text.to_lowercase();
+        let kind = match directive.as_str() {
+            ".equ" => SymbolKind::Address,
+            ".const" => SymbolKind::Constant,
+            ".data" => return self.parse_data(&line, code),
+            _ => {
+                let error = line[0].error(ParseErrorKind::UnknownDirective);
+                return Err(match closest_match(&directive, DIRECTIVES.iter().copied()) {
+                    Some(candidate) => error.with_hint(format!("did you mean `{}`?", candidate)),
+                    None => error
+                });
+            }
+        };
+        if line.len() != 3 || !is_symbol_name(&line[1].text) {
+            return Err(ParseError::new(ParseErrorKind::MalformedSymbolDefinition, line[0].line, line[0].span.start..line[line.len() - 1].span.end, code.trim())
+                .with_hint(format!("symbols are defined like `{} NAME {}5`, names consist of letters, digits and `_` and cannot start with a digit",
+                    directive, if kind == SymbolKind::Constant { "=" } else { "" })));
+        }
+        let name = &line[1];
+        if let Some(symbol) = self.symbols.iter().find(|x| x.name == name.text) {
+            return Err(name.error(ParseErrorKind::RedefinedSymbol).with_hint(format!("`{}` was already defined at line {}", symbol.name, symbol.line)));
+        }
+        if self.entries.contains(&Entry::Label(name.text.clone())) {
+            return Err(name.error(ParseErrorKind::RedefinedSymbol).with_hint(format!("`{}` is already used as a label", name.text)));
+        }
+        // the `=` prefix is optional for constants
+        let prefix = if kind == SymbolKind::Constant && line[2].text.starts_with('=') { 1 } else { 0 };
+        let value = self.evaluate(&line[2], prefix, ParseErrorKind::MalformedExpression)?;
+        self.symbols.push(Symbol { name: name.text.clone(), value, kind, line: name.line });
+        Ok(())
+    }
+
+    // values are stored in consecutive cells starting from the address, each cell can be initialized once
+    fn parse_data(&mut self, line: &[Token], code: &str) -> Result<(), ParseError> {
+        let malformed = || ParseError::new(ParseErrorKind::MalformedDataDefinition, line[0].line, line[0].span.start..line[line.len() - 1].span.end, code.trim())
+            .with_hint("initial memory contents are defined like `.data 10: 1, 1, 2`".to_string());
+        let start = line[0].span.end;
+        let colon = start + code[start..].find(':').ok_or_else(malformed)?;
+        let address_token = sub_token(code, start..colon, line[0].line).ok_or_else(malformed)?;
+        let address = self.evaluate_address(&address_token, 0, ParseErrorKind::MalformedExpression)?;
+        let mut values = Vec::<(Token, ImmediateValue)>::new();
+        let mut value_start = colon + 1;
+        for piece in code[colon + 1..].split(',') {
+            let value_token = sub_token(code, value_start..value_start + piece.len(), line[0].line).ok_or_else(malformed)?;
+            value_start += piece.len() + 1;
+            let value = self.evaluate(&value_token, 0, ParseErrorKind::MalformedExpression)?;
+            values.push((value_token, value));
+        }
+        for (offset, (token, value)) in values.into_iter().enumerate() {
+            let cell = address.checked_add(offset).ok_or(ParseError::new(ParseErrorKind::IllegalAddress, token.line, token.span.clone(), &token.text)
+                .with_hint("value would be stored past the highest possible address".to_string()))?;
+            if let Some((_, defined_line)) = self.data.get(&cell) {
+                return Err(token.error(ParseErrorKind::RedefinedData).with_hint(format!("cell {} is already initialized at line {}", cell, defined_line)));
+            }
+            self.data.insert(cell, (value, token.line));
+        }
+        Ok(())
+    }
+
+    // operands of jumps which are not numbers are label names, taken as they are
+    fn parse_argument(&self, argument: &Token, instruction: &str) -> Result<Operand, ParseError> {
+        let jump = matches!(instruction, "jump" | "jgtz" | "jzero" | "jeof");
+        if let Some(value) = argument.text.strip_prefix('=') {
+            match value.parse::<ImmediateValue>() {
+                Ok(value) => Ok(Operand::Immediate(value)),
+                Err(_) => Ok(Operand::Immediate(self.evaluate(argument, 1, ParseErrorKind::MalformedImmediateValue)?))
+            }
+        } else if let Some(value) = argument.text.strip_prefix('^') {
+            match value.parse::<usize>() {
+                Ok(value) => Ok(Operand::IntermediateAddress(value)),
+                Err(_) => {
+                    self.check_constant_address(argument, 1, instruction)?;
+                    Ok(Operand::IntermediateAddress(self.evaluate_address(argument, 1, ParseErrorKind::MalformedIntermediateAddress)?))
+                }
+            }
+        } else {
+            match argument.text.parse::<usize>() {
+                Ok(value) => Ok(Operand::ImmediateAddress(value)),
+                Err(_) if jump => Ok(Operand::Label(0)),
+                Err(_) if is_symbol_name(&argument.text) && !self.symbols.iter().any(|x| x.name == argument.text) => Ok(Operand::Label(0)),
+                Err(_) => {
+                    self.check_constant_address(argument, 0, instruction)?;
+                    Ok(Operand::ImmediateAddress(self.evaluate_address(argument, 0, ParseErrorKind::MalformedExpression)?))
+                }
+            }
+        }
+    }
+
+    // .const symbol on its own names a value, so it cannot be used as an address (it still can be part of an expression, e.g. `ARR+N`)
+    fn check_constant_address(&self, argument: &Token, prefix: usize, instruction: &str) -> Result<(), ParseError> {
+        let text = &argument.text[prefix..];
+        let name = text.trim_start_matches('(').trim_end_matches(')');
+        let symbol = match self.symbols.iter().find(|x| x.name == name) {
+            Some(symbol) if symbol.kind == SymbolKind::Constant => symbol,
+            _ => return Ok(())
+        };
+        let hint = match instruction {
+            "store" | "read" => format!("`{}` is defined with `.const` at line {}, memory cells are named with `.equ`", name, symbol.line),
+            _ => format!("`{}` is defined with `.const`; did you mean `{} ={}`?", name, instruction, name)
+        };
+        Err(ParseError::new(ParseErrorKind::ConstantAsAddress, argument.line, argument.span.start + prefix..argument.span.end, name).with_hint(hint))
+    }
+
+    // evaluates constant expression making up the argument after prefix of provided length,
+    // malformed expressions are reported with provided kind
+    fn evaluate(&self, argument: &Token, prefix: usize, malformed: ParseErrorKind) -> Result<ImmediateValue, ParseError> {
+        let text = &argument.text[prefix..];
+        let offset = argument.span.start + prefix;
+        expression::evaluate(text, |name| self.symbols.iter().find(|x| x.name == name).map(|x| ImmediateValue::clone(&x.value)))
+            .map_err(|(kind, range)| {
+                let span = offset + range.start..offset + range.end;
+                match kind {
+                    ParseErrorKind::UndefinedSymbol => self.undefined_symbol_error(&text[range], argument.line, span),
+                    ParseErrorKind::MalformedExpression => ParseError::new(malformed, argument.line, span, &argument.text)
+                        .with_hint("operands are integers, symbols or expressions using `+`, `-`, `*`, `/` and parentheses, e.g. `=BASE+2*N`".to_string()),
+                    ParseErrorKind::ExpressionOverflow => ParseError::new(kind, argument.line, span, &text[range])
+                        .with_hint("values have to fit in a memory cell".to_string()),
+                    _ => ParseError::new(kind, argument.line, span, &text[range])
+                }
+            })
+    }
+
+    fn evaluate_address(&self, argument: &Token, prefix: usize, malformed: ParseErrorKind) -> Result<usize, ParseError> {
+        let value = self.evaluate(argument, prefix, malformed)?;
+        let text = &argument.text[prefix..];
+        value.to_address().ok_or(ParseError::new(ParseErrorKind::IllegalAddress, argument.line, argument.span.start + prefix..argument.span.end, text)
+            .with_hint(format!("`{}` evaluates to {}, addresses have to be non-negative", text, value)))
+    }
+
+    fn undefined_symbol_error(&self, name: &str, line: usize, span: Range<usize>) -> ParseError {
+        let error = ParseError::new(ParseErrorKind::UndefinedSymbol, line, span, name);
+        match closest_match(name, self.symbols.iter().map(|x| x.name.as_str())) {
+            Some(candidate) => error.with_hint(format!("did you mean `{}`?", candidate)),
+            None => error.with_hint("symbols have to be defined with `.equ` or `.const` before use".to_string())
+        }
+    }
+
+    // name in operand of instruction other than jump, once all labels and symbols are known
+    fn unresolved_name_error(&self, instruction: &str, argument: &Token) -> ParseError {
+        if self.entries.contains(&Entry::Label(argument.text.clone())) {
+            return illegal_label_error(instruction, argument);
+        }
+        match self.symbols.iter().find(|x| x.name == argument.text) {
+            Some(symbol) => ParseError::new(ParseErrorKind::UndefinedSymbol, argument.line, argument.span.clone(), &argument.text)
+                .with_hint(format!("`{}` is defined at line {}, symbols have to be defined before use", symbol.name, symbol.line)),
+            None => self.undefined_symbol_error(&argument.text, argument.line, argument.span.clone())
         }
     }
 
-    fn parse_line(&mut self, line: String, line_number: usize) -> Result<(), String> {
-        let original_line = line.clone();
-        let line = line.split(";").collect::<Vec<&str>>()[0].trim();
-        let line: Vec<String> = line.split_whitespace().map(|s| -> String { s.to_string() }).collect();
-        if line.len() == 0 { return Ok(()); } // ignore empty lines
-        if line[0].ends_with(":") { // try to parse label
-            let label = line[0].trim_end_matches(":");
-            if line.len() > 1 { return Err(format!("malformed label definition \"{}\" at line {}", original_line, line_number)); }
-            if label.is_empty() { return Err(format!("empty label \"{}\" at line {}", &line[0], line_number)); }
-            if self.entries.contains(&Entry::Label(label.to_string())) { return Err(format!("redefined label \"{}\" at line {}", label, line_number)); }
+    fn parse_line(&mut self, line: String, line_number: usize) -> Result<(), ParseError> {
+        let code = line.split(';').next().unwrap_or("");
+        let line = tokenize(code, line_number);
+        if line.is_empty() { return Ok(()); } // ignore empty lines
+        if line[0].text.starts_with('.') {
+            self.parse_directive(line, code)?;
+        } else if line[0].text.ends_with(':') { // try to parse label
+            let label = line[0].text.trim_end_matches(':');
+            if line.len() > 1 {
+                return Err(ParseError::new(ParseErrorKind::MalformedLabelDefinition, line_number, line[1].span.start..line[line.len() - 1].span.end, code.trim())
+                    .with_hint("labels have to be defined on their own line".to_string()));
+            }
+            if label.is_empty() { return Err(line[0].error(ParseErrorKind::EmptyLabel)); }
+            if self.entries.contains(&Entry::Label(label.to_string())) {
+                return Err(ParseError::new(ParseErrorKind::RedefinedLabel, line_number, line[0].span.start..line[0].span.end - 1, label));
+            }
+            if let Some(symbol) = self.symbols.iter().find(|x| x.name == label) {
+                return Err(ParseError::new(ParseErrorKind::RedefinedLabel, line_number, line[0].span.start..line[0].span.end - 1, label)
+                    .with_hint(format!("`{}` is already defined as a symbol at line {}", label, symbol.line)));
+            }
             self.entries.push(Entry::Label(label.to_string()));
+            self.entry_lines.push(line_number);
         } else { // try to parse instruction
-            match self.parse_instruction(line, original_line.as_str(), line_number) {
-                Ok(entry) => self.entries.push(entry),
-                Err(message) => return Err(message)
-            }
+            let entry = self.parse_instruction(line, code.trim())?;
+            self.entries.push(entry);
+            self.entry_lines.push(line_number);
         }
         Ok(())
     }
 
-    fn finalize(&self) -> Result<Vec<Instruction>, String> {
+    fn finalize(&self) -> Result<Program, Vec<ParseError>> {
         let mut result = Vec::<Instruction>::new();
-        for entry in self.entries.iter() {
+        let mut source_lines = Vec::<usize>::new();
+        let mut labels = Vec::<(String, usize)>::new();
+        let mut errors = Vec::<ParseError>::new();
+        for (entry, line) in self.entries.iter().zip(self.entry_lines.iter()) {
+            if !matches!(entry, Entry::Label(_)) { source_lines.push(*line); }
             match entry {
                 Entry::Instruction(instruction) => result.push((*instruction).clone()),
                 Entry::UnfinishedInstruction(instruction, label) => {
-                    let search_result = self.entries.iter().position(|x| { *x == Entry::Label(label.clone()) } );
+                    let search_result = self.entries.iter().position(|x| { *x == Entry::Label(label.text.clone()) } );
                     match search_result {
                         Some(position) => {
                             let labels_until_jumped_instruction = self.entries.iter().enumerate().filter(
-                                |&x| -> bool { 
+                                |&x| -> bool {
                                     if let Entry::Label(_) = *x.1 {
                                         if x.0 < position + 1 { return true }
                                         false
-                                    } else { false } 
+                                    } else { false }
                                 }
                             ).count();
-                            let instruction_pointer = position - labels_until_jumped_instruction + 1;
+                            let instruction_pointer = position + 1 - labels_until_jumped_instruction;
                             match instruction {
                                 Instruction::Jump(_) => { result.push(Instruction::Jump(Operand::Label(instruction_pointer))); }
                                 Instruction::Jgtz(_) => { result.push(Instruction::Jgtz(Operand::Label(instruction_pointer))); }
                                 Instruction::Jzero(_) => { result.push(Instruction::Jzero(Operand::Label(instruction_pointer))); }
+                                Instruction::Jeof(_) => { result.push(Instruction::Jeof(Operand::Label(instruction_pointer))); }
                                 _ => panic!("unexpected value wrapped in Entry::UnfinishedInstruction")
                             }
                         },
-                        None => return Err(format!("label {} not defined", label))
+                        None => {
+                            let defined_labels = self.entries.iter().filter_map(|x| if let Entry::Label(name) = x { Some(name.as_str()) } else { None });
+                            let error = label.error(ParseErrorKind::UndefinedLabel);
+                            errors.push(match closest_match(&label.text, defined_labels) {
+                                Some(candidate) => error.with_hint(format!("did you mean `{}`?", candidate)),
+                                None => error
+                            });
+                        }
                     }
                 }
-                Entry::Label(_) => {}
+                Entry::UnresolvedName(instruction, argument) => errors.push(self.unresolved_name_error(instruction, argument)),
+                Entry::Label(label) => labels.push((label.clone(), result.len()))
             }
         }
-        Ok(result)
+        if !errors.is_empty() { return Err(errors); }
+        let mut data: Vec<(MemoryLocation, ImmediateValue)> = self.data.iter().map(|(address, (value, _))| (*address, ImmediateValue::clone(value))).collect();
+        data.sort_by_key(|x| x.0);
+        Ok(Program {
+            instructions: result,
+            source_lines,
+            labels,
+            symbols: self.symbols.clone(),
+            data
+        })
     }
 }
 
-fn parse_argument(argument: String, line_number: usize) -> Result<Operand, String> {
-    if argument.starts_with("=") {
-        match argument[1..].parse::<i64>() {
-            Ok(value) => return Ok(Operand::Immediate(value)),
-            Err(_) => return Err(format!("malformed immediate value {} at line {}", argument, line_number))
-        }
-    } else if argument.starts_with("^") {
-        match argument[1..].parse::<usize>() {
-            Ok(value) => return Ok(Operand::IntermediateAddress(value)),
-            Err(_) => return Err(format!("malformed intermediate address value {} at line {}", argument, line_number))
+fn tokenize(code: &str, line_number: usize) -> Vec<Token> {
+    let mut tokens = Vec::<Token>::new();
+    let mut start: Option<usize> = None;
+    for (index, character) in code.char_indices() {
+        match (character.is_whitespace(), start) {
+            (true, Some(token_start)) => {
+                tokens.push(Token { text: code[token_start..index].to_string(), line: line_number, span: token_start..index });
+                start = None;
+            },
+            (false, None) => start = Some(index),
+            _ => {}
         }
-    } else {
-        match argument.parse::<usize>() {
-            Ok(value) => return Ok(Operand::ImmediateAddress(value)),
-            Err(_) => return Ok(Operand::Label(0))
+    }
+    if let Some(token_start) = start {
+        tokens.push(Token { text: code[token_start..].to_string(), line: line_number, span: token_start..code.len() });
+    }
+    tokens
+}
+
+// token made of provided part of the line with surrounding whitespace removed, None if nothing is left
+fn sub_token(code: &str, range: Range<usize>, line: usize) -> Option<Token> {
+    let text = &code[range.clone()];
+    let start = range.start + (text.len() - text.trim_start().len());
+    let text = text.trim();
+    if text.is_empty() { return None; }
+    Some(Token { text: text.to_string(), line, span: start..start + text.len() })
+}
+
+// letters, digits and underscores, not starting with a digit
+fn is_symbol_name(text: &str) -> bool {
+    text.chars().next().is_some_and(|x| x.is_alphabetic() || x == '_') && text.chars().all(|x| x.is_alphanumeric() || x == '_')
+}
+
+fn parse_tape_selector(instruction: &str, selector: &Token) -> Result<TapeNumber, ParseError> {
+    if instruction != "read" && instruction != "write" {
+        return Err(selector.error(ParseErrorKind::IllegalTapeSelector)
+            .with_hint("only `read` and `write` can select a tape".to_string()));
+    }
+    selector.text[1..].parse::<TapeNumber>().map_err(|_| selector.error(ParseErrorKind::MalformedTapeNumber)
+        .with_hint("tape numbers have to be non-negative integers, e.g. `@1`".to_string()))
+}
+
+// bare name in operand of instruction other than jump, which is neither a number nor a defined symbol;
+// whether it is a label has to wait until the whole program is parsed
+fn label_argument(instruction: &str, argument: &Token) -> Result<Entry, ParseError> {
+    if is_symbol_name(&argument.text) {
+        return Ok(Entry::UnresolvedName(instruction.to_string(), argument.clone()));
+    }
+    Err(illegal_label_error(instruction, argument))
+}
+
+fn illegal_label_error(instruction: &str, argument: &Token) -> ParseError {
+    argument.error(ParseErrorKind::IllegalLabelArgument)
+        .with_hint(format!("`{}` cannot take a label; only `jump`, `jgtz`, `jzero` and `jeof` can", instruction))
+}
+
+fn illegal_immediate_error(instruction: &str, argument: &Token) -> ParseError {
+    argument.error(ParseErrorKind::IllegalImmediateArgument)
+        .with_hint(format!("`{}` cannot take an immediate; did you mean `{} {}`?", instruction, instruction, argument.text.trim_start_matches('=')))
+}
+
+fn illegal_non_label_error(instruction: &str, argument: &Token) -> ParseError {
+    argument.error(ParseErrorKind::IllegalNonLabelArgument)
+        .with_hint(format!("`{}` can only take a label name", instruction))
+}
+
+// Levenshtein distance between two strings (counted in characters)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + if x == *y { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
         }
+        previous = current;
     }
+    previous[b.len()]
 }
 
-pub fn parse_input(filepath: &String) -> Result<Vec<Instruction>, String> {
-    let file = File::open(filepath);
-    if let Ok(file) = file {
-        let reader = BufReader::new(file);
-        let mut state = State::new();
-        for (index, line) in reader.lines().enumerate() {
-            match line {
-                Ok(line) => {
-                    if let Err(message) = state.parse_line(line, index + 1) {
-                        return Err(message);
-                    }
-                },
-                Err(_) => return Err(format!("reading line {} failed", index + 1))
+// finds the candidate closest to the word, if it is close enough to be considered a typo
+fn closest_match<'a>(word: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (word.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (edit_distance(word, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// parses whole input, recovering from errors on a line-by-line basis; on failure returns every
+// diagnostic found (including undefined labels), sorted by line
+pub fn parse_reader(reader: impl BufRead) -> Result<Program, Vec<ParseError>> {
+    let mut state = State::new();
+    let mut errors = Vec::<ParseError>::new();
+    let mut source_lines = Vec::<String>::new();
+    for (index, line) in reader.lines().enumerate() {
+        match line {
+            Ok(line) => {
+                source_lines.push(line.clone());
+                if let Err(error) = state.parse_line(line, index + 1) {
+                    errors.push(error);
+                }
+            },
+            Err(_) => {
+                errors.push(ParseError::new(ParseErrorKind::ReadFailed, index + 1, 0..0, ""));
+                break;
             }
         }
-        state.finalize()
     }
-    else {
-        Err(format!("could not open \"{}\" file to read", filepath))
+    match state.finalize() {
+        Ok(instructions) if errors.is_empty() => return Ok(instructions),
+        Ok(_) => {},
+        Err(mut finalize_errors) => errors.append(&mut finalize_errors)
+    }
+    for error in errors.iter_mut() {
+        if let Some(source_line) = error.line.checked_sub(1).and_then(|x| source_lines.get(x)) {
+            error.source_line = source_line.clone();
+        }
+    }
+    errors.sort_by_key(|error| (error.line, error.span.start));
+    Err(errors)
+}
+
+pub fn parse_str(source: &str) -> Result<Program, Vec<ParseError>> {
+    parse_reader(source.as_bytes())
+}
+
+pub fn parse_input(filepath: &String) -> Result<Program, Vec<ParseError>> {
+    match File::open(filepath) {
+        Ok(file) => parse_reader(BufReader::new(file)),
+        Err(_) => Err(vec![ParseError::new(ParseErrorKind::FileOpenFailed, 0, 0..0, filepath)])
     }
 }
@@ -1,222 +1,684 @@
 use crate::memory::Memory;
 use crate::instructions::*;
 use crate::tape::Tape;
+use crate::error::{ExecutionError, ExecutionErrorKind, TapeError};
+use crate::value::{ArithmeticOperation, Value};
+use crate::cost::{self, Cost, CostCounter};
+use crate::watchpoint::{Access, WatchCondition, WatchHit, Watchpoint};
+use crate::history::{self, History, Step};
+use crate::snapshot::Snapshot;
+use crate::trace::TraceRecord;
+use crate::profile::Profile;
+
+// behavior of arithmetic instructions (ADD, SUB, MULT, DIV) when result does not fit in a memory cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowMode {
+    Trap,     // stop execution with an error
+    Wrap,     // wrap around (two's complement)
+    Saturate  // clamp to the closest representable value
+}
+
+// behavior of memory accesses past the end of memory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryPolicy {
+    Strict,          // stop execution with an error
+    Lenient,         // ignore writes, read zeros
+    AutoGrow(usize)  // extend memory on write, up to provided number of cells (accesses past that limit are errors)
+}
+
+// behavior of READ when there are no more values on input tape
+#[derive(Debug, Clone, PartialEq)]
+pub enum EofPolicy {
+    Error,                    // stop execution with an error
+    Halt,                     // halt the program as if HALT was executed
+    Sentinel(ImmediateValue), // read provided value
+    Flag                      // leave the cell unchanged and carry on, the program is expected to test it with JEOF
+}
 
 pub struct Processor<T: Tape> {
     instructions: Vec<Instruction>,
     instruction_pointer: InstructionLocation,
     memory: Memory,
     halted: bool,
+    end_of_input: bool, // whether the last READ reached the end of its input tape, tested by JEOF
+    overflow_mode: OverflowMode,
+    memory_policy: MemoryPolicy,
+    eof_policy: EofPolicy,
+    tape_eof_policies: Vec<Option<EofPolicy>>, // policies of single tapes overriding eof_policy
+    cost: CostCounter,
+    step_count: u64,
+    step_limit: Option<u64>,
+    watchpoints: Vec<Watchpoint>,
+    watch_hits: Vec<WatchHit>,
+    history: History,
+    current_step: Option<Step>, // undo record of the instruction being executed, if history is enabled
+    input_positions: Vec<u64>,  // number of values read from every input tape
+    output_positions: Vec<u64>, // number of values written to every output tape
+    tracing: bool,
+    current_trace: Option<TraceRecord>, // trace record of the instruction being executed, if tracing is enabled
+    trace_record: Option<TraceRecord>,  // trace record of the last executed instruction
+    profile: Option<Profile>,
     tapes: T
 }
 
 impl<T: Tape> Processor<T> {
-    pub fn new(instructions: Vec<Instruction>, memory_size: usize, tapes: T) -> Self {
-        if memory_size == 0 {
+    // initial memory contents (see Program::data) are stored before the first instruction is executed,
+    // fails if they do not fit in memory; panics if memory size is 0
+    pub fn new(instructions: Vec<Instruction>, mut memory: Memory, data: Vec<(MemoryLocation, ImmediateValue)>, tapes: T) -> Result<Self, ExecutionError> {
+        if memory.size() == 0 {
             panic!("error: tried to create processor with memory size of 0");
         }
-        Processor {
-            instructions: instructions,
+        for (address, value) in data {
+            if !memory.contains(address) {
+                return Err(ExecutionError::new(ExecutionErrorKind::DataOutOfBounds(address, memory.size()), 0, None));
+            }
+            memory.set(value, address);
+        }
+        Ok(Processor {
+            instructions,
             instruction_pointer: 0,
-            memory: Memory::new(memory_size),
+            memory,
             halted: false,
-            tapes: tapes
-        }
+            end_of_input: false,
+            overflow_mode: OverflowMode::Trap,
+            memory_policy: MemoryPolicy::Lenient,
+            eof_policy: EofPolicy::Error,
+            tape_eof_policies: Vec::new(),
+            cost: CostCounter::new(),
+            step_count: 0,
+            step_limit: None,
+            watchpoints: Vec::new(),
+            watch_hits: Vec::new(),
+            history: History::new(),
+            current_step: None,
+            input_positions: Vec::new(),
+            output_positions: Vec::new(),
+            tracing: false,
+            current_trace: None,
+            trace_record: None,
+            profile: None,
+            tapes
+        })
     }
 
     pub fn is_halted(&self) -> bool {
         self.halted
     }
 
-    fn get_intermediate_address(&self, intermediate_address: MemoryLocation) -> Result<MemoryLocation, String> {
-        let x = self.memory.get(intermediate_address);
-        if x < 0 { return Err("intermediate pointer must be non-negative".to_string()); }
-        Ok(x as MemoryLocation)
+    pub fn set_overflow_mode(&mut self, overflow_mode: OverflowMode) {
+        self.overflow_mode = overflow_mode;
+    }
+
+    pub fn get_overflow_mode(&self) -> OverflowMode {
+        self.overflow_mode
+    }
+
+    pub fn set_memory_policy(&mut self, memory_policy: MemoryPolicy) {
+        self.memory_policy = memory_policy;
+    }
+
+    pub fn get_memory_policy(&self) -> MemoryPolicy {
+        self.memory_policy
+    }
+
+    pub fn set_eof_policy(&mut self, eof_policy: EofPolicy) {
+        self.eof_policy = eof_policy;
+    }
+
+    pub fn get_eof_policy(&self) -> EofPolicy {
+        self.eof_policy.clone()
+    }
+
+    // policy used only by provided tape, None makes the tape follow the one set with set_eof_policy
+    pub fn set_tape_eof_policy(&mut self, tape: TapeNumber, eof_policy: Option<EofPolicy>) {
+        *history::tape_entry(&mut self.tape_eof_policies, tape) = eof_policy;
+    }
+
+    // policy in effect for provided tape
+    pub fn get_tape_eof_policy(&self, tape: TapeNumber) -> EofPolicy {
+        self.tape_eof_policies.get(tape).cloned().flatten().unwrap_or_else(|| self.eof_policy.clone())
+    }
+
+    // limits number of instructions the processor executes, None means no limit
+    pub fn set_step_limit(&mut self, step_limit: Option<u64>) {
+        self.step_limit = step_limit;
+    }
+
+    pub fn get_step_limit(&self) -> Option<u64> {
+        self.step_limit
+    }
+
+    // number of instructions executed so far
+    pub fn get_step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    pub fn get_instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn get_instruction_pointer(&self) -> InstructionLocation {
+        self.instruction_pointer
+    }
+
+    pub fn set_instruction_pointer(&mut self, instruction_pointer: InstructionLocation) {
+        self.instruction_pointer = instruction_pointer;
+    }
+
+    pub fn get_memory_size(&self) -> usize {
+        self.memory.size()
+    }
+
+    pub fn contains_address(&self, address: MemoryLocation) -> bool {
+        self.memory.contains(address)
+    }
+
+    // reads memory cell bypassing memory policy and cost accounting (for inspecting state between instructions)
+    pub fn get_memory_cell(&self, address: MemoryLocation) -> ImmediateValue {
+        self.memory.get(address)
+    }
+
+    // writes memory cell bypassing memory policy and cost accounting, returns false if address is past the end of memory
+    pub fn set_memory_cell(&mut self, address: MemoryLocation, value: ImmediateValue) -> bool {
+        if !self.memory.contains(address) { return false; }
+        self.memory.set(value, address);
+        true
+    }
+
+    pub fn get_accumulator(&self) -> ImmediateValue {
+        self.memory.get(0)
+    }
+
+    pub fn set_accumulator(&mut self, value: ImmediateValue) {
+        self.memory.set(value, 0);
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    pub fn remove_watchpoint(&mut self, index: usize) -> Option<Watchpoint> {
+        if index >= self.watchpoints.len() { return None; }
+        Some(self.watchpoints.remove(index))
+    }
+
+    pub fn get_watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    // returns watchpoints triggered since the last call, in order of memory accesses
+    pub fn take_watch_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(&mut self.watch_hits)
+    }
+
+    fn check_watchpoints(&mut self, access: Access, address: MemoryLocation, old_value: &ImmediateValue, new_value: &ImmediateValue) {
+        for (index, watchpoint) in self.watchpoints.iter().enumerate() {
+            if watchpoint.address != address { continue; }
+            let triggered = match (&watchpoint.condition, &access) {
+                (WatchCondition::Read, Access::Read) | (WatchCondition::Write, Access::Write) => true,
+                (WatchCondition::Value(comparison, value), Access::Write) => comparison.evaluate(new_value, value),
+                _ => false
+            };
+            if triggered {
+                self.watch_hits.push(WatchHit {
+                    watchpoint: index,
+                    access: access.clone(),
+                    address,
+                    instruction_pointer: self.instruction_pointer,
+                    instruction: self.instructions[self.instruction_pointer].clone(),
+                    old_value: ImmediateValue::clone(old_value),
+                    new_value: ImmediateValue::clone(new_value)
+                });
+            }
+        }
+    }
+
+    // time and space cost of instructions executed so far
+    pub fn get_cost(&self) -> Cost {
+        self.cost.get()
+    }
+
+    // number of most recently executed instructions which can be undone, 0 (default) disables recording
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.set_limit(limit);
+    }
+
+    pub fn get_history_limit(&self) -> usize {
+        self.history.get_limit()
+    }
+
+    // the earliest step number the processor can go back to
+    pub fn get_history_start(&self) -> u64 {
+        self.step_count - self.history.len() as u64
+    }
+
+    // undoes the last executed instruction, returns false if there is nothing recorded to undo
+    // changes made with set_* methods are not recorded, so they are not undone
+    pub fn step_back(&mut self) -> bool {
+        match self.history.pop() {
+            Some(step) => {
+                self.undo(step);
+                self.step_count -= 1;
+                true
+            },
+            None => false
+        }
+    }
+
+    // undoes instructions until provided number of instructions is executed, returns false
+    // (without changing anything) if that step is in the future or is no longer recorded
+    pub fn rewind_to(&mut self, step_count: u64) -> bool {
+        if step_count > self.step_count || step_count < self.get_history_start() { return false; }
+        while self.step_count > step_count {
+            self.step_back();
+        }
+        true
+    }
+
+    // makes processor record what every executed instruction did (see take_trace_record)
+    pub fn set_tracing(&mut self, tracing: bool) {
+        self.tracing = tracing;
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.tracing
+    }
+
+    // returns trace record of the last successfully executed instruction (if tracing is enabled and it was not taken yet)
+    pub fn take_trace_record(&mut self) -> Option<TraceRecord> {
+        self.trace_record.take()
+    }
+
+    // makes processor count executions of every instruction, enabling it again resets the counts
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.profile = if profiling { Some(Profile::new(self.instructions.len())) } else { None };
+    }
+
+    pub fn get_profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    // numbers of values read from and written to provided tape so far
+    pub fn get_tape_positions(&self, tape: TapeNumber) -> (u64, u64) {
+        (self.input_positions.get(tape).copied().unwrap_or(0), self.output_positions.get(tape).copied().unwrap_or(0))
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            instructions: self.instructions.clone(),
+            instruction_pointer: self.instruction_pointer,
+            halted: self.halted,
+            end_of_input: self.end_of_input,
+            step_count: self.step_count,
+            sparse_memory: self.memory.is_sparse(),
+            memory_size: self.memory.size(),
+            cells: self.memory.cells(),
+            input_positions: self.input_positions.clone(),
+            output_positions: self.output_positions.clone()
+        }
+    }
+
+    // replaces state of the machine with provided one and moves tapes to positions recorded in it,
+    // settings (overflow mode, memory policy, limits, watchpoints) are kept, while cost and history start anew,
+    // returns numbers of tapes which could not be moved, so their values no longer match the recorded positions
+    pub fn restore(&mut self, snapshot: Snapshot) -> Vec<TapeNumber> {
+        let mut memory = if snapshot.sparse_memory { Memory::new_sparse() } else { Memory::new(snapshot.memory_size.max(1)) };
+        for (address, value) in snapshot.cells {
+            memory.set(value, address);
+        }
+        self.instructions = snapshot.instructions;
+        self.instruction_pointer = snapshot.instruction_pointer;
+        self.memory = memory;
+        self.set_profiling(self.profile.is_some());
+        self.halted = snapshot.halted;
+        self.end_of_input = snapshot.end_of_input;
+        self.step_count = snapshot.step_count;
+        self.cost = CostCounter::new();
+        self.watch_hits.clear();
+        self.history.clear();
+        self.input_positions = snapshot.input_positions;
+        self.output_positions = snapshot.output_positions;
+        (0..self.input_positions.len().max(self.output_positions.len())).filter(|&tape| {
+            let (input_position, output_position) = self.get_tape_positions(tape);
+            !self.tapes.seek_tape(tape, input_position, output_position)
+        }).collect()
+    }
+
+    fn undo(&mut self, step: Step) {
+        for (address, value) in step.writes.into_iter().rev() {
+            self.memory.set(value, address);
+        }
+        self.memory.resize(step.memory_size);
+        if let Some((tape, value)) = step.input {
+            self.history.unread(tape, value);
+            self.input_positions[tape] -= 1;
+        }
+        if let Some(tape) = step.output {
+            self.history.unwrite(tape);
+            self.output_positions[tape] -= 1;
+        }
+        self.cost.restore(step.cost, &step.cell_sizes);
+        self.instruction_pointer = step.instruction_pointer;
+        self.halted = step.halted;
+        self.end_of_input = step.end_of_input;
+    }
+
+    fn count_access(&mut self, address: MemoryLocation, value: &ImmediateValue) {
+        let previous_size = self.cost.count_access(address, value);
+        if let (Some(step), Some(size)) = (&mut self.current_step, previous_size) {
+            step.cell_sizes.push((address, size));
+        }
+    }
+
+    fn read_input(&mut self, tape: TapeNumber) -> Result<ImmediateValue, TapeError> {
+        let value = match self.history.take_pending_input(tape) {
+            Some(value) => value,
+            None => self.tapes.read_from(tape)?
+        };
+        *history::tape_entry(&mut self.input_positions, tape) += 1;
+        if let Some(step) = &mut self.current_step {
+            step.input = Some((tape, ImmediateValue::clone(&value)));
+        }
+        if let Some(trace) = &mut self.current_trace {
+            trace.input = Some(ImmediateValue::clone(&value));
+        }
+        Ok(value)
+    }
+
+    fn write_output(&mut self, tape: TapeNumber, value: ImmediateValue) -> Result<(), TapeError> {
+        let output = ImmediateValue::clone(&value);
+        if !self.history.take_suppressed_output(tape) {
+            self.tapes.write_to(tape, value)?;
+        }
+        *history::tape_entry(&mut self.output_positions, tape) += 1;
+        if let Some(trace) = &mut self.current_trace {
+            trace.output = Some(output);
+        }
+        if let Some(step) = &mut self.current_step {
+            step.output = Some(tape);
+        }
+        Ok(())
+    }
+
+    fn read_memory(&mut self, address: MemoryLocation) -> Result<ImmediateValue, ExecutionErrorKind> {
+        let contained = self.memory.contains(address);
+        if !contained {
+            match self.memory_policy {
+                MemoryPolicy::Strict => return Err(ExecutionErrorKind::MemoryOutOfBounds(address)),
+                MemoryPolicy::AutoGrow(limit) if address >= limit => return Err(ExecutionErrorKind::MemoryOutOfBounds(address)),
+                _ => {} // memory returns zeros past its end
+            }
+        }
+        let value = self.memory.get(address);
+        // cells past the end of memory do not take space
+        if contained {
+            self.count_access(address, &value);
+        }
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints(Access::Read, address, &value, &value);
+        }
+        Ok(value)
+    }
+
+    fn write_memory(&mut self, value: ImmediateValue, address: MemoryLocation) -> Result<(), ExecutionErrorKind> {
+        if !self.memory.contains(address) {
+            match self.memory_policy {
+                MemoryPolicy::Strict => return Err(ExecutionErrorKind::MemoryOutOfBounds(address)),
+                MemoryPolicy::Lenient => return Ok(()),
+                MemoryPolicy::AutoGrow(limit) => {
+                    if address >= limit { return Err(ExecutionErrorKind::MemoryOutOfBounds(address)); }
+                    // grow geometrically to avoid reallocating on every sequential write
+                    let new_size = (address + 1).max(self.memory.size().saturating_mul(2)).min(limit);
+                    self.memory.resize(new_size);
+                }
+            }
+        }
+        self.count_access(address, &value);
+        if !self.watchpoints.is_empty() {
+            let old_value = self.memory.get(address);
+            self.check_watchpoints(Access::Write, address, &old_value, &value);
+        }
+        if let Some(step) = &mut self.current_step {
+            step.writes.push((address, self.memory.get(address)));
+        }
+        if let Some(trace) = &mut self.current_trace {
+            trace.write = Some((address, ImmediateValue::clone(&value)));
+        }
+        self.memory.set(value, address);
+        Ok(())
+    }
+
+    fn calculate(&self, operation: ArithmeticOperation, lhs: ImmediateValue, rhs: ImmediateValue) -> Result<ImmediateValue, ExecutionErrorKind> {
+        if operation == ArithmeticOperation::Div && rhs.is_zero() {
+            return Err(ExecutionErrorKind::DivisionByZero);
+        }
+        match lhs.calculate(operation, &rhs, self.overflow_mode) {
+            Some(result) => Ok(result),
+            None => Err(ExecutionErrorKind::ArithmeticOverflow(lhs, rhs))
+        }
+    }
+
+    // None means the pointer is too big to be an address, which is past the end of any memory, so lenient policy ignores the access
+    fn get_intermediate_address(&mut self, intermediate_address: MemoryLocation) -> Result<Option<MemoryLocation>, ExecutionErrorKind> {
+        let x = self.read_memory(intermediate_address)?;
+        if x.is_negative() { return Err(ExecutionErrorKind::NegativeIndirectPointer(intermediate_address, x)); }
+        match x.to_address() {
+            Some(address) => Ok(Some(address)),
+            None if self.memory_policy == MemoryPolicy::Lenient => Ok(None),
+            None => Err(ExecutionErrorKind::PointerOutOfBounds(x))
+        }
+    }
+
+    // resolves operand of instructions operating on values (LOAD, ADD, SUB, MULT, DIV, WRITE)
+    fn get_operand_value(&mut self, operand: &Operand) -> Result<ImmediateValue, ExecutionErrorKind> {
+        match operand {
+            Operand::Immediate(value) => Ok(ImmediateValue::clone(value)), // values are not Copy with "bigint" feature
+            Operand::ImmediateAddress(value) => {
+                self.trace_effective_address(*value);
+                self.read_memory(*value)
+            },
+            Operand::IntermediateAddress(value) => match self.get_intermediate_address(*value)? {
+                Some(address) => {
+                    self.trace_effective_address(address);
+                    self.read_memory(address)
+                },
+                None => Ok(ImmediateValue::default())
+            },
+            Operand::Label(_) => Err(ExecutionErrorKind::IllegalOperand)
+        }
+    }
+
+    // resolves operand of instructions operating on memory locations (STORE, READ), None means the write is ignored
+    fn get_operand_address(&mut self, operand: &Operand) -> Result<Option<MemoryLocation>, ExecutionErrorKind> {
+        let address = match operand {
+            Operand::ImmediateAddress(value) => *value,
+            Operand::IntermediateAddress(value) => match self.get_intermediate_address(*value)? {
+                Some(address) => address,
+                None => return Ok(None)
+            },
+            _ => return Err(ExecutionErrorKind::IllegalOperand)
+        };
+        self.trace_effective_address(address);
+        Ok(Some(address))
+    }
+
+    fn count_branch(&mut self, taken: bool) {
+        if let Some(profile) = &mut self.profile {
+            profile.count_branch(self.instruction_pointer, taken);
+        }
+    }
+
+    fn trace_effective_address(&mut self, address: MemoryLocation) {
+        if let Some(trace) = &mut self.current_trace {
+            trace.effective_address = Some(address);
+        }
+    }
+
+    // resolves operand of jump instructions (JUMP, JGTZ, JZERO)
+    fn get_operand_label(&self, operand: &Operand) -> Result<InstructionLocation, ExecutionErrorKind> {
+        match operand {
+            Operand::Label(value) => Ok(*value),
+            _ => Err(ExecutionErrorKind::IllegalOperand)
+        }
     }
 
     // TODO: replace most Err(...) with panic! (after implementing source code parser, if correctly implemented, should not happen)
-    pub fn execute_instruction(&mut self) -> Result<(), String> {
+    pub fn execute_instruction(&mut self) -> Result<(), ExecutionError> {
         if self.instruction_pointer >= self.instructions.len() {
             self.halted = true;
-            return Err("instruction pointer run out of instruction space, processor halted".to_string());
+            return Err(ExecutionError::new(ExecutionErrorKind::InstructionPointerOutOfRange, self.instruction_pointer, None));
         }
-        let current_instruction = &self.instructions[self.instruction_pointer];
-        match current_instruction {
-            Instruction::Load(operand) => {
-                let value_to_load;
-                match operand {
-                    Operand::Immediate(value) => value_to_load = *value,
-                    Operand::ImmediateAddress(value) => value_to_load = self.memory.get(*value),
-                    Operand::IntermediateAddress(value) => { 
-                        let address = self.get_intermediate_address(*value);
-                        match address {
-                            Ok(address) => value_to_load = self.memory.get(address),
-                            Err(message) => return Err(message)
-                        };
-                        
-                    }
-                    _ => return Err("load operation cannot be provided with label".to_string())
+        let current_instruction = self.instructions[self.instruction_pointer].clone();
+        if let Some(limit) = self.step_limit {
+            if self.step_count >= limit {
+                return Err(ExecutionError::new(ExecutionErrorKind::StepLimitExceeded(limit), self.instruction_pointer, Some(current_instruction)));
+            }
+        }
+        let instruction_pointer = self.instruction_pointer;
+        let logarithmic_cost = cost::instruction_cost(&current_instruction, &self.memory);
+        if self.history.is_enabled() {
+            self.current_step = Some(Step::new(self.instruction_pointer, self.halted, self.end_of_input, self.memory.size(), self.cost.get()));
+        }
+        if self.tracing {
+            self.current_trace = Some(TraceRecord::new(self.step_count, self.instruction_pointer, current_instruction.clone(), self.memory.get(0)));
+        }
+        match self.execute(&current_instruction) {
+            Ok(()) => {
+                if let Some(profile) = &mut self.profile {
+                    profile.count_execution(instruction_pointer);
                 }
-                self.memory.set(value_to_load, 0);
+                self.cost.count_instruction(logarithmic_cost);
+                self.step_count += 1;
+                if let Some(step) = self.current_step.take() {
+                    self.history.push(step);
+                }
+                if let Some(mut trace) = self.current_trace.take() {
+                    trace.accumulator_after = self.memory.get(0);
+                    self.trace_record = Some(trace);
+                }
+                Ok(())
+            },
+            Err(kind) => {
+                self.current_trace = None;
+                // roll back partially executed instruction, so it can be retried (e.g. after fixing memory in debugger)
+                if let Some(step) = self.current_step.take() {
+                    self.undo(step);
+                }
+                Err(ExecutionError::new(kind, self.instruction_pointer, Some(current_instruction)))
+            }
+        }
+    }
+
+    fn execute(&mut self, instruction: &Instruction) -> Result<(), ExecutionErrorKind> {
+        match instruction {
+            Instruction::Load(operand) => {
+                let value_to_load = self.get_operand_value(operand)?;
+                self.write_memory(value_to_load, 0)?;
                 self.instruction_pointer += 1;
             },
             Instruction::Store(operand) => {
-                let accumulator = self.memory.get(0);
-                let address_to_store;
-                match operand {
-                    Operand::ImmediateAddress(value) => address_to_store = *value,
-                    Operand::IntermediateAddress(value) => { 
-                        let address = self.get_intermediate_address(*value);
-                        match address {
-                            Ok(address) => address_to_store = address,
-                            Err(message) => return Err(message)
-                        };
-                    },
-                    _ => return Err("store operation cannot be provided with immediate or label".to_string())
+                let accumulator = self.read_memory(0)?;
+                if let Some(address_to_store) = self.get_operand_address(operand)? {
+                    self.write_memory(accumulator, address_to_store)?;
                 }
-                self.memory.set(accumulator, address_to_store);
                 self.instruction_pointer += 1;
             },
             Instruction::Add(operand) => {
-                let value_to_add;
-                match operand {
-                    Operand::Immediate(value) => value_to_add = *value,
-                    Operand::ImmediateAddress(value) => value_to_add = self.memory.get(*value),
-                    Operand::IntermediateAddress(value) => { 
-                        let address = self.get_intermediate_address(*value);
-                        match address {
-                            Ok(address) => value_to_add = self.memory.get(address),
-                            Err(message) => return Err(message)
-                        };
-                    }
-                    _ => { return Err("add operation cannot be provided with label".to_string()) }
-                }
-                let new_accumulator = self.memory.get(0) + value_to_add;
-                self.memory.set(new_accumulator, 0);
+                let value_to_add = self.get_operand_value(operand)?;
+                let accumulator = self.read_memory(0)?;
+                let new_accumulator = self.calculate(ArithmeticOperation::Add, accumulator, value_to_add)?;
+                self.write_memory(new_accumulator, 0)?;
                 self.instruction_pointer += 1;
             },
             Instruction::Sub(operand) => {
-                let value_to_sub;
-                match operand {
-                    Operand::Immediate(value) => value_to_sub = *value,
-                    Operand::ImmediateAddress(value) => value_to_sub = self.memory.get(*value),
-                    Operand::IntermediateAddress(value) => { 
-                        let address = self.get_intermediate_address(*value);
-                        match address {
-                            Ok(address) => value_to_sub = self.memory.get(address),
-                            Err(message) => return Err(message)
-                        };
-                    }
-                    _ => { return Err("sub operation cannot be provided with label".to_string()) }
-                }
-                let new_accumulator = self.memory.get(0) - value_to_sub;
-                self.memory.set(new_accumulator, 0);
+                let value_to_sub = self.get_operand_value(operand)?;
+                let accumulator = self.read_memory(0)?;
+                let new_accumulator = self.calculate(ArithmeticOperation::Sub, accumulator, value_to_sub)?;
+                self.write_memory(new_accumulator, 0)?;
                 self.instruction_pointer += 1;
             },
             Instruction::Mult(operand) => {
-                let value_to_mult;
-                match operand {
-                    Operand::Immediate(value) => value_to_mult = *value,
-                    Operand::ImmediateAddress(value) => value_to_mult = self.memory.get(*value),
-                    Operand::IntermediateAddress(value) => { 
-                        let address = self.get_intermediate_address(*value);
-                        match address {
-                            Ok(address) => value_to_mult = self.memory.get(address),
-                            Err(message) => return Err(message)
-                        };
-                    }
-                    _ => { return Err("mult operation cannot be provided with label".to_string()) }
-                }
-                let new_accumulator = self.memory.get(0) * value_to_mult;
-                self.memory.set(new_accumulator, 0);
+                let value_to_mult = self.get_operand_value(operand)?;
+                let accumulator = self.read_memory(0)?;
+                let new_accumulator = self.calculate(ArithmeticOperation::Mult, accumulator, value_to_mult)?;
+                self.write_memory(new_accumulator, 0)?;
                 self.instruction_pointer += 1;
             },
             Instruction::Div(operand) => {
-                let value_to_div;
-                match operand {
-                    Operand::Immediate(value) => value_to_div = *value,
-                    Operand::ImmediateAddress(value) => value_to_div = self.memory.get(*value),
-                    Operand::IntermediateAddress(value) => { 
-                        let address = self.get_intermediate_address(*value);
-                        match address {
-                            Ok(address) => value_to_div = self.memory.get(address),
-                            Err(message) => return Err(message)
-                        };
-                    }
-                    _ => { return Err("div operation cannot be provided with label".to_string()) }
-                }
-                if value_to_div == 0 {
-                    return Err("division by zero".to_string());
-                }
-                let new_accumulator = self.memory.get(0) / value_to_div;
-                self.memory.set(new_accumulator, 0);
+                let value_to_div = self.get_operand_value(operand)?;
+                let accumulator = self.read_memory(0)?;
+                let new_accumulator = self.calculate(ArithmeticOperation::Div, accumulator, value_to_div)?;
+                self.write_memory(new_accumulator, 0)?;
                 self.instruction_pointer += 1;
             },
-            Instruction::Read(operand) => {
-                let address_to_store;
-                match operand {
-                    Operand::ImmediateAddress(value) => address_to_store = *value,
-                    Operand::IntermediateAddress(value) => { 
-                        let address = self.get_intermediate_address(*value);
-                        match address {
-                            Ok(address) => address_to_store = address,
-                            Err(message) => return Err(message)
-                        };
+            Instruction::Read(operand, tape) => {
+                let address_to_store = self.get_operand_address(operand)?;
+                let value = match self.read_input(*tape) {
+                    Ok(value) => {
+                        self.end_of_input = false;
+                        value
                     },
-                    _ => return Err("read operation cannot be provided with immediate or label".to_string())
-                }
-                let tape_value = self.tapes.read();
-                match tape_value {
-                    Some(value) => self.memory.set(value, address_to_store),
-                    None => return Err("tried to read, but tape error occured (end of input or malformed input)".to_string())
+                    Err(TapeError::EndOfTape) => match self.get_tape_eof_policy(*tape) {
+                        EofPolicy::Error => return Err(ExecutionErrorKind::Tape(TapeError::EndOfTape)),
+                        EofPolicy::Halt => {
+                            self.end_of_input = true;
+                            self.halted = true;
+                            return Ok(());
+                        },
+                        EofPolicy::Sentinel(value) => {
+                            self.end_of_input = true;
+                            value
+                        },
+                        EofPolicy::Flag => {
+                            self.end_of_input = true;
+                            self.instruction_pointer += 1;
+                            return Ok(());
+                        }
+                    },
+                    Err(error) => return Err(ExecutionErrorKind::Tape(error))
+                };
+                self.cost.count_input(&value);
+                if let Some(address_to_store) = address_to_store {
+                    self.write_memory(value, address_to_store)?;
                 }
                 self.instruction_pointer += 1;
             },
-            Instruction::Write(operand) => {
-                let value_to_write;
-                match operand {
-                    Operand::Immediate(value) => value_to_write = *value,
-                    Operand::ImmediateAddress(value) => value_to_write = self.memory.get(*value),
-                    Operand::IntermediateAddress(value) => { 
-                        let address = self.get_intermediate_address(*value);
-                        match address {
-                            Ok(address) => value_to_write = self.memory.get(address),
-                            Err(message) => return Err(message)
-                        };
-                    },
-                    _ => return Err("write operation cannot be provided with label".to_string())
-                }
-                self.tapes.write(value_to_write);
+            Instruction::Write(operand, tape) => {
+                let value_to_write = self.get_operand_value(operand)?;
+                self.write_output(*tape, value_to_write).map_err(ExecutionErrorKind::Tape)?;
                 self.instruction_pointer += 1;
             },
             Instruction::Jump(operand) => {
-                match operand {
-                    Operand::Label(value) => self.instruction_pointer = *value,
-                    _ => return Err("jump operation cannot be provided with immediate, immediate address or intermediate address".to_string())
-                }
+                self.instruction_pointer = self.get_operand_label(operand)?;
             },
             Instruction::Jgtz(operand) => {
-                match operand {
-                    Operand::Label(value) => {
-                        if self.memory.get(0) > 0 {
-                            self.instruction_pointer = *value
-                        } else {
-                            self.instruction_pointer += 1;
-                        }
-                    },
-                    _ => return Err("jump operation cannot be provided with immediate, immediate address or intermediate address".to_string())
+                let target = self.get_operand_label(operand)?;
+                let taken = self.read_memory(0)?.is_positive();
+                self.count_branch(taken);
+                if taken {
+                    self.instruction_pointer = target;
+                } else {
+                    self.instruction_pointer += 1;
                 }
             },
             Instruction::Jzero(operand) => {
-                match operand {
-                    Operand::Label(value) => {
-                        if self.memory.get(0) == 0 {
-                            self.instruction_pointer = *value
-                        } else {
-                            self.instruction_pointer += 1;
-                        }
-                    },
-                    _ => return Err("jump operation cannot be provided with immediate, immediate address or intermediate address".to_string())
+                let target = self.get_operand_label(operand)?;
+                let taken = self.read_memory(0)?.is_zero();
+                self.count_branch(taken);
+                if taken {
+                    self.instruction_pointer = target;
+                } else {
+                    self.instruction_pointer += 1;
+                }
+            },
+            Instruction::Jeof(operand) => {
+                let target = self.get_operand_label(operand)?;
+                let taken = self.end_of_input;
+                self.count_branch(taken);
+                if taken {
+                    self.instruction_pointer = target;
+                } else {
+                    self.instruction_pointer += 1;
                 }
             },
             Instruction::Halt => {
@@ -228,7 +690,7 @@ impl<T: Tape> Processor<T> {
 
     #[allow(dead_code)]
     pub fn get_current_state(&self) ->  (&Instruction, usize) {
-        return (&self.instructions[self.instruction_pointer], self.instruction_pointer)
+        (&self.instructions[self.instruction_pointer], self.instruction_pointer)
     }
 
     #[allow(dead_code)]
@@ -237,4 +699,4 @@ impl<T: Tape> Processor<T> {
         self.memory.dump();
     }
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,77 @@
+use crate::instructions::{Instruction, InstructionLocation};
+use crate::program::Program;
+use std::collections::HashMap;
+
+// execution counts gathered while running a program (see Processor::set_profiling)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub executions: Vec<u64>,         // number of executions of every instruction
+    pub branches_taken: Vec<u64>,     // for conditional jumps (JGTZ, JZERO, JEOF), number of times the jump was taken
+    pub branches_not_taken: Vec<u64>
+}
+
+impl Profile {
+    pub fn new(instruction_count: usize) -> Self {
+        Profile {
+            executions: vec![0; instruction_count],
+            branches_taken: vec![0; instruction_count],
+            branches_not_taken: vec![0; instruction_count]
+        }
+    }
+
+    pub(crate) fn count_execution(&mut self, location: InstructionLocation) {
+        self.executions[location] += 1;
+    }
+
+    pub(crate) fn count_branch(&mut self, location: InstructionLocation, taken: bool) {
+        if taken {
+            self.branches_taken[location] += 1;
+        } else {
+            self.branches_not_taken[location] += 1;
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.executions.iter().sum()
+    }
+
+    // executions summed over blocks of instructions following every label (up to the next one),
+    // in order of appearance; instructions before the first label are reported under None
+    pub fn by_label<'a>(&self, program: &'a Program) -> Vec<(Option<&'a str>, u64)> {
+        let mut blocks = Vec::<(Option<&str>, u64)>::new();
+        for (location, count) in self.executions.iter().enumerate() {
+            let label = program.enclosing_label(location);
+            match blocks.last_mut() {
+                Some(block) if block.0 == label => block.1 += count,
+                _ => blocks.push((label, *count))
+            }
+        }
+        blocks
+    }
+
+    // source code with number of executions next to every line holding an instruction, e.g.
+    //       5 |   12 |     jgtz loop   [taken: 4, not taken: 1]
+    pub fn annotate(&self, program: &Program, source: &str) -> String {
+        let mut line_locations = HashMap::<usize, InstructionLocation>::new();
+        for (location, line_number) in program.source_lines.iter().enumerate() {
+            line_locations.entry(*line_number).or_insert(location);
+        }
+        let mut lines = vec![format!("{:>8} | {:>4} | source", "count", "line")];
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let (count, branches) = match line_locations.get(&line_number).copied() {
+                Some(location) => {
+                    let branches = match program.instructions[location] {
+                        Instruction::Jgtz(_) | Instruction::Jzero(_) | Instruction::Jeof(_) => format!("   [taken: {}, not taken: {}]",
+                            self.branches_taken[location], self.branches_not_taken[location]),
+                        _ => String::new()
+                    };
+                    (self.executions[location].to_string(), branches)
+                },
+                None => (String::new(), String::new())
+            };
+            lines.push(format!("{:>8} | {:>4} | {}{}", count, line_number, line, branches));
+        }
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,63 @@
+use crate::instructions::{ImmediateValue, Instruction, InstructionLocation, MemoryLocation};
+use crate::value::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Address,  // defined with .equ, names a memory cell
+    Constant  // defined with .const, names a value
+}
+
+// named value defined in source code, substituted in operands
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub value: ImmediateValue,
+    pub kind: SymbolKind,
+    pub line: usize // 1-based source line of definition
+}
+
+// parsed program along with information mapping it back to its source code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub source_lines: Vec<usize>,                   // 1-based source line of every instruction
+    pub labels: Vec<(String, InstructionLocation)>, // labels in order of definition, with location of instruction they mark
+    pub symbols: Vec<Symbol>,                       // symbols in order of definition
+    pub data: Vec<(MemoryLocation, ImmediateValue)> // initial memory contents (.data) in ascending order of addresses
+}
+
+impl Program {
+    pub fn find_label(&self, name: &str) -> Option<InstructionLocation> {
+        self.labels.iter().find(|x| x.0 == name).map(|x| x.1)
+    }
+
+    // labels marking provided location
+    pub fn labels_at(&self, location: InstructionLocation) -> impl Iterator<Item = &str> {
+        self.labels.iter().filter(move |x| x.1 == location).map(|x| x.0.as_str())
+    }
+
+    // the last label defined at or before provided location, i.e. the label "block" instruction belongs to
+    pub fn enclosing_label(&self, location: InstructionLocation) -> Option<&str> {
+        self.labels.iter().rev().find(|x| x.1 <= location).map(|x| x.0.as_str())
+    }
+
+    pub fn find_symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|x| x.name == name)
+    }
+
+    // names of .equ symbols referring to provided memory cell
+    pub fn symbols_at(&self, address: MemoryLocation) -> impl Iterator<Item = &str> {
+        self.symbols.iter()
+            .filter(move |x| x.kind == SymbolKind::Address && x.value.to_address() == Some(address))
+            .map(|x| x.name.as_str())
+    }
+
+    // number of memory cells needed to hold initial memory contents
+    pub fn data_end(&self) -> usize {
+        self.data.last().map_or(0, |x| x.0 + 1)
+    }
+
+    pub fn source_line(&self, location: InstructionLocation) -> Option<usize> {
+        self.source_lines.get(location).copied()
+    }
+}
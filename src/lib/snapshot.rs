@@ -0,0 +1,288 @@
+use crate::instructions::{ImmediateValue, Instruction, InstructionLocation, MemoryLocation, Operand, TapeNumber};
+use crate::error::{SnapshotError, SnapshotErrorKind};
+use std::{fs, str::FromStr};
+
+const HEADER: &str = "rasm-snapshot 1";
+
+// complete state of the machine, which can be restored later (see Processor::snapshot and Processor::restore)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub instructions: Vec<Instruction>,
+    pub instruction_pointer: InstructionLocation,
+    pub halted: bool,
+    pub end_of_input: bool,                             // whether READ reached the end of input tape (see EofPolicy)
+    pub step_count: u64,
+    pub sparse_memory: bool,
+    pub memory_size: usize,                             // ignored for sparse memory
+    pub cells: Vec<(MemoryLocation, ImmediateValue)>,   // non-zero cells in ascending order of addresses
+    pub input_positions: Vec<u64>,                      // number of values read from every input tape
+    pub output_positions: Vec<u64>                      // number of values written to every output tape
+}
+
+impl Snapshot {
+    // plain text format, one field per line, e.g.
+    //   rasm-snapshot 1
+    //   instruction_pointer 3
+    //   halted false
+    //   end_of_input false
+    //   step_count 17
+    //   input_position 1 0
+    //   output_position 0 2
+    //   memory dense 512
+    //   instructions 2
+    //   load =1
+    //   jump 0
+    //   cells 1
+    //   0 1
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            HEADER.to_string(),
+            format!("instruction_pointer {}", self.instruction_pointer),
+            format!("halted {}", self.halted),
+            format!("end_of_input {}", self.end_of_input),
+            format!("step_count {}", self.step_count),
+            format!("input_position {}", join_positions(&self.input_positions)),
+            format!("output_position {}", join_positions(&self.output_positions)),
+            if self.sparse_memory { "memory sparse".to_string() } else { format!("memory dense {}", self.memory_size) },
+            format!("instructions {}", self.instructions.len())
+        ];
+        lines.extend(self.instructions.iter().map(|x| x.to_string()));
+        lines.push(format!("cells {}", self.cells.len()));
+        lines.extend(self.cells.iter().map(|(address, value)| format!("{} {}", address, value)));
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    pub fn from_text(text: &str) -> Result<Snapshot, SnapshotError> {
+        let mut lines = text.lines().enumerate().map(|(index, line)| (index + 1, line.trim())).filter(|x| !x.1.is_empty());
+        match lines.next() {
+            Some((_, HEADER)) => {},
+            Some((line_number, line)) => return Err(SnapshotError::new(SnapshotErrorKind::UnknownFormat, line_number, line)),
+            None => return Err(SnapshotError::new(SnapshotErrorKind::UnknownFormat, 0, ""))
+        }
+        let mut instruction_pointer: Option<InstructionLocation> = None;
+        let mut halted = false;
+        let mut end_of_input = false;
+        let mut step_count = 0u64;
+        let mut input_positions = Vec::<u64>::new();
+        let mut output_positions = Vec::<u64>::new();
+        let mut memory: Option<(bool, usize)> = None;
+        let mut instructions: Option<Vec<Instruction>> = None;
+        let mut cells = Vec::<(MemoryLocation, ImmediateValue)>::new();
+        while let Some((line_number, line)) = lines.next() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            match key {
+                "instruction_pointer" => instruction_pointer = Some(parse_value(value, line_number)?),
+                "halted" => halted = parse_value(value, line_number)?,
+                "end_of_input" => end_of_input = parse_value(value, line_number)?,
+                "step_count" => step_count = parse_value(value, line_number)?,
+                "input_position" => input_positions = parse_positions(value, line_number)?,
+                "output_position" => output_positions = parse_positions(value, line_number)?,
+                "memory" => memory = match value.split_once(' ') {
+                    _ if value == "sparse" => Some((true, 0)),
+                    Some(("dense", size)) => match parse_value::<usize>(size, line_number)? {
+                        0 => return Err(SnapshotError::new(SnapshotErrorKind::MalformedValue, line_number, size)),
+                        size => Some((false, size))
+                    },
+                    _ => return Err(SnapshotError::new(SnapshotErrorKind::MalformedValue, line_number, value))
+                },
+                "instructions" => {
+                    let count: usize = parse_value(value, line_number)?;
+                    let mut section = Vec::<Instruction>::new();
+                    for _ in 0..count {
+                        let (line_number, line) = lines.next().ok_or(SnapshotError::new(SnapshotErrorKind::UnexpectedEnd, 0, key))?;
+                        section.push(parse_instruction(line).ok_or(SnapshotError::new(SnapshotErrorKind::MalformedInstruction, line_number, line))?);
+                    }
+                    instructions = Some(section);
+                },
+                "cells" => {
+                    let count: usize = parse_value(value, line_number)?;
+                    for _ in 0..count {
+                        let (line_number, line) = lines.next().ok_or(SnapshotError::new(SnapshotErrorKind::UnexpectedEnd, 0, key))?;
+                        let (address, value) = line.split_once(' ').ok_or(SnapshotError::new(SnapshotErrorKind::MalformedValue, line_number, line))?;
+                        cells.push((parse_value(address, line_number)?, parse_value(value.trim(), line_number)?));
+                    }
+                },
+                _ => return Err(SnapshotError::new(SnapshotErrorKind::UnknownField, line_number, key))
+            }
+        }
+
+        let instruction_pointer = instruction_pointer.ok_or(SnapshotError::new(SnapshotErrorKind::MissingField, 0, "instruction_pointer"))?;
+        let (sparse_memory, memory_size) = memory.ok_or(SnapshotError::new(SnapshotErrorKind::MissingField, 0, "memory"))?;
+        let instructions = instructions.ok_or(SnapshotError::new(SnapshotErrorKind::MissingField, 0, "instructions"))?;
+        if let Some((address, _)) = cells.iter().find(|x| !sparse_memory && x.0 >= memory_size) {
+            return Err(SnapshotError::new(SnapshotErrorKind::CellOutOfBounds, 0, &address.to_string()));
+        }
+        cells.sort_by_key(|x| x.0);
+        Ok(Snapshot {
+            instructions,
+            instruction_pointer,
+            halted,
+            end_of_input,
+            step_count,
+            sparse_memory,
+            memory_size,
+            cells,
+            input_positions,
+            output_positions
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Snapshot, SnapshotError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Snapshot::from_text(&text),
+            Err(_) => Err(SnapshotError::new(SnapshotErrorKind::FileOpenFailed, 0, path))
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SnapshotError> {
+        fs::write(path, self.to_text()).map_err(|_| SnapshotError::new(SnapshotErrorKind::WriteFailed, 0, path))
+    }
+}
+
+// positions of consecutive tapes, separated by spaces
+fn join_positions(positions: &[u64]) -> String {
+    positions.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+fn parse_positions(value: &str, line_number: usize) -> Result<Vec<u64>, SnapshotError> {
+    value.split_whitespace().map(|x| parse_value(x, line_number)).collect()
+}
+
+fn parse_value<V: FromStr>(value: &str, line_number: usize) -> Result<V, SnapshotError> {
+    value.parse::<V>().map_err(|_| SnapshotError::new(SnapshotErrorKind::MalformedValue, line_number, value))
+}
+
+// parses instruction in the form written by its Display implementation (jump targets are instruction indices)
+fn parse_instruction(line: &str) -> Option<Instruction> {
+    let mut tokens = line.split_whitespace();
+    let mnemonic = tokens.next()?;
+    let operand = tokens.next();
+    let address_operand = |operand: Option<&str>| -> Option<Operand> {
+        let operand = operand?;
+        if let Some(value) = operand.strip_prefix('=') { return value.parse::<ImmediateValue>().ok().map(Operand::Immediate); }
+        if let Some(address) = operand.strip_prefix('^') { return address.parse::<MemoryLocation>().ok().map(Operand::IntermediateAddress); }
+        operand.parse::<MemoryLocation>().ok().map(Operand::ImmediateAddress)
+    };
+    let label_operand = |operand: Option<&str>| operand?.parse::<InstructionLocation>().ok().map(Operand::Label);
+    let tape = match tokens.next() {
+        Some(selector) => selector.strip_prefix('@')?.parse::<TapeNumber>().ok()?,
+        None => 0
+    };
+    if tokens.next().is_some() { return None; }
+    match (mnemonic, operand) {
+        ("load", _) => address_operand(operand).map(Instruction::Load),
+        ("store", _) => address_operand(operand).map(Instruction::Store),
+        ("add", _) => address_operand(operand).map(Instruction::Add),
+        ("sub", _) => address_operand(operand).map(Instruction::Sub),
+        ("mult", _) => address_operand(operand).map(Instruction::Mult),
+        ("div", _) => address_operand(operand).map(Instruction::Div),
+        ("read", _) => address_operand(operand).map(|x| Instruction::Read(x, tape)),
+        ("write", _) => address_operand(operand).map(|x| Instruction::Write(x, tape)),
+        ("jump", _) => label_operand(operand).map(Instruction::Jump),
+        ("jgtz", _) => label_operand(operand).map(Instruction::Jgtz),
+        ("jzero", _) => label_operand(operand).map(Instruction::Jzero),
+        ("jeof", _) => label_operand(operand).map(Instruction::Jeof),
+        ("halt", None) => Some(Instruction::Halt),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+    use crate::parser::parse_str;
+    use crate::processor::Processor;
+    use crate::tape::VecTape;
+
+    fn value(x: i64) -> ImmediateValue {
+        ImmediateValue::from(x)
+    }
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            instructions: vec![
+                Instruction::Read(Operand::ImmediateAddress(1), 2),
+                Instruction::Load(Operand::Immediate(value(-5))),
+                Instruction::Store(Operand::IntermediateAddress(1)),
+                Instruction::Jeof(Operand::Label(0)),
+                Instruction::Write(Operand::Immediate(value(7)), 1),
+                Instruction::Halt
+            ],
+            instruction_pointer: 3,
+            halted: false,
+            end_of_input: true,
+            step_count: 17,
+            sparse_memory: false,
+            memory_size: 64,
+            cells: vec![(0, value(-5)), (1, value(3)), (63, value(12))],
+            input_positions: vec![0, 0, 1],
+            output_positions: vec![0, 2]
+        }
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let snapshot = snapshot();
+        assert_eq!(Snapshot::from_text(&snapshot.to_text()), Ok(snapshot));
+        let sparse = Snapshot { sparse_memory: true, memory_size: 0, halted: true, cells: vec![(usize::MAX, value(1))], ..self::snapshot() };
+        assert_eq!(Snapshot::from_text(&sparse.to_text()), Ok(sparse));
+    }
+
+    #[test]
+    fn optional_fields_and_cell_order() {
+        let text = "rasm-snapshot 1\ninstruction_pointer 1\nmemory dense 8\ninstructions 1\nhalt\n\ncells 2\n5 2\n3 -1\n";
+        let snapshot = Snapshot::from_text(text).unwrap();
+        assert!(!snapshot.halted && !snapshot.end_of_input);
+        assert_eq!(snapshot.step_count, 0);
+        assert!(snapshot.input_positions.is_empty() && snapshot.output_positions.is_empty());
+        assert_eq!(snapshot.cells, vec![(3, value(-1)), (5, value(2))]);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| Snapshot::from_text(text).map(|_| ()).unwrap_err();
+        assert_eq!(error(""), SnapshotError::new(SnapshotErrorKind::UnknownFormat, 0, ""));
+        assert_eq!(error("rasm-snapshot 2"), SnapshotError::new(SnapshotErrorKind::UnknownFormat, 1, "rasm-snapshot 2"));
+        assert_eq!(error("rasm-snapshot 1\nmemory dense 8\ninstructions 0"), SnapshotError::new(SnapshotErrorKind::MissingField, 0, "instruction_pointer"));
+        assert_eq!(error("rasm-snapshot 1\ninstruction_pointer 0\nmemory dense 0"), SnapshotError::new(SnapshotErrorKind::MalformedValue, 3, "0"));
+        assert_eq!(error("rasm-snapshot 1\ninstruction_pointer 0\ninstructions 2\nload =1"), SnapshotError::new(SnapshotErrorKind::UnexpectedEnd, 0, "instructions"));
+        assert_eq!(error("rasm-snapshot 1\ninstructions 2\nload =1\njump start"), SnapshotError::new(SnapshotErrorKind::MalformedInstruction, 4, "jump start"));
+        assert_eq!(error("rasm-snapshot 1\ninstruction_pointer 0\nmemory dense 4\ninstructions 0\ncells 1\n4 1"), SnapshotError::new(SnapshotErrorKind::CellOutOfBounds, 0, "4"));
+        assert_eq!(error("rasm-snapshot 1\naccumulator 5"), SnapshotError::new(SnapshotErrorKind::UnknownField, 2, "accumulator"));
+    }
+
+    // sums input values into cell 2 and writes partial sums, until the input runs out
+    const SUM: &str = "loop:\nread 1\nload 1\nadd 2\nstore 2\nwrite 2\njump loop\n";
+
+    fn run(processor: &mut Processor<VecTape>, steps: Option<u64>) {
+        let mut executed = 0;
+        while steps.is_none_or(|x| executed < x) && processor.execute_instruction().is_ok() {
+            executed += 1;
+        }
+    }
+
+    #[test]
+    fn restore_continues_interrupted_run() {
+        let program = parse_str(SUM).unwrap();
+        let input: Vec<ImmediateValue> = (1..=5).map(value).collect();
+        let mut uninterrupted = Processor::new(program.instructions.clone(), Memory::new(8), Vec::new(), VecTape::new(input.clone())).unwrap();
+        run(&mut uninterrupted, None);
+
+        let mut interrupted = Processor::new(program.instructions.clone(), Memory::new(8), Vec::new(), VecTape::new(input.clone())).unwrap();
+        run(&mut interrupted, Some(15));
+        let text = interrupted.snapshot().to_text();
+        let mut resumed = Processor::new(program.instructions, Memory::new(1), Vec::new(), VecTape::new(input)).unwrap();
+        assert!(resumed.restore(Snapshot::from_text(&text).unwrap()).is_empty());
+        assert_eq!(resumed.get_step_count(), 15);
+        assert_eq!(resumed.get_memory_size(), 8);
+        assert_eq!(resumed.get_tape_positions(0), (3, 2));
+        run(&mut resumed, None);
+
+        assert_eq!(resumed.get_memory_cell(2), value(15));
+        assert_eq!(resumed.get_step_count(), uninterrupted.get_step_count());
+        assert_eq!(resumed.get_tape_positions(0), uninterrupted.get_tape_positions(0));
+    }
+}
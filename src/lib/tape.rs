@@ -1,4 +1,360 @@
+use crate::instructions::{ImmediateValue, TapeNumber};
+use crate::error::TapeError;
+use crate::value::Value;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, LineWriter, Write};
+
 pub trait Tape {
-    fn read(&mut self) -> Option<i64>;
-    fn write(&mut self, value: i64);
-} 
\ No newline at end of file
+    fn read(&mut self) -> Result<ImmediateValue, TapeError>;
+    fn write(&mut self, value: ImmediateValue);
+    // moves the tape to provided positions (numbers of values read and written so far),
+    // called when processor state is restored from a snapshot, returns false if the tape cannot be moved there
+    fn seek(&mut self, _input_position: u64, _output_position: u64) -> bool { false }
+
+    // numbered tapes used by READ and WRITE with tape selector (see TapeSet), a single tape has only number 0
+    fn read_from(&mut self, tape: TapeNumber) -> Result<ImmediateValue, TapeError> {
+        if tape != 0 { return Err(TapeError::UnknownTape(tape)); }
+        self.read()
+    }
+    fn write_to(&mut self, tape: TapeNumber, value: ImmediateValue) -> Result<(), TapeError> {
+        if tape != 0 { return Err(TapeError::UnknownTape(tape)); }
+        self.write(value);
+        Ok(())
+    }
+    // tapes which do not exist have nothing to move, so only their start can be sought
+    fn seek_tape(&mut self, tape: TapeNumber, input_position: u64, output_position: u64) -> bool {
+        if tape != 0 { return input_position == 0 && output_position == 0; }
+        self.seek(input_position, output_position)
+    }
+}
+
+impl<T: Tape + ?Sized> Tape for Box<T> {
+    fn read(&mut self) -> Result<ImmediateValue, TapeError> {
+        (**self).read()
+    }
+    fn write(&mut self, value: ImmediateValue) {
+        (**self).write(value)
+    }
+    fn seek(&mut self, input_position: u64, output_position: u64) -> bool {
+        (**self).seek(input_position, output_position)
+    }
+    fn read_from(&mut self, tape: TapeNumber) -> Result<ImmediateValue, TapeError> {
+        (**self).read_from(tape)
+    }
+    fn write_to(&mut self, tape: TapeNumber, value: ImmediateValue) -> Result<(), TapeError> {
+        (**self).write_to(tape, value)
+    }
+    fn seek_tape(&mut self, tape: TapeNumber, input_position: u64, output_position: u64) -> bool {
+        (**self).seek_tape(tape, input_position, output_position)
+    }
+}
+
+// preloaded input and captured output, e.g. for running programs from code
+pub struct VecTape {
+    input: Vec<ImmediateValue>,
+    input_position: usize,
+    output: Vec<ImmediateValue>
+}
+
+impl VecTape {
+    pub fn new(input: Vec<ImmediateValue>) -> Self {
+        VecTape {
+            input,
+            input_position: 0,
+            output: Vec::new()
+        }
+    }
+
+    pub fn get_output(&self) -> &[ImmediateValue] {
+        &self.output
+    }
+}
+
+impl Tape for VecTape {
+    fn read(&mut self) -> Result<ImmediateValue, TapeError> {
+        let value = self.input.get(self.input_position).cloned().ok_or(TapeError::EndOfTape)?;
+        self.input_position += 1;
+        Ok(value)
+    }
+    fn write(&mut self, value: ImmediateValue) {
+        self.output.push(value);
+    }
+    fn seek(&mut self, input_position: u64, output_position: u64) -> bool {
+        self.input_position = input_position as usize;
+        self.output.truncate(output_position as usize);
+        true
+    }
+}
+
+// reads whitespace separated values from any reader and writes values to any writer, one per line
+pub struct ReaderWriterTape<R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+    tokens: VecDeque<String>, // not yet read values from the current line
+    values_read: u64
+}
+
+impl<R: BufRead, W: Write> ReaderWriterTape<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        ReaderWriterTape {
+            reader,
+            writer,
+            tokens: VecDeque::new(),
+            values_read: 0
+        }
+    }
+
+    fn next_token(&mut self) -> Result<String, TapeError> {
+        while self.tokens.is_empty() {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return Err(TapeError::EndOfTape),
+                Ok(_) => self.tokens.extend(line.split_whitespace().map(|x| x.to_string())),
+                Err(error) => return Err(TapeError::Io(error.to_string()))
+            }
+        }
+        Ok(self.tokens.pop_front().unwrap())
+    }
+}
+
+impl<R: BufRead, W: Write> Tape for ReaderWriterTape<R, W> {
+    // malformed value is skipped, so reading again continues with the next one
+    fn read(&mut self) -> Result<ImmediateValue, TapeError> {
+        let token = self.next_token()?;
+        let value = token.parse::<ImmediateValue>().map_err(|_| TapeError::Malformed(token))?;
+        self.values_read += 1;
+        Ok(value)
+    }
+    fn write(&mut self, value: ImmediateValue) {
+        let _ = writeln!(self.writer, "{}", value);
+    }
+    // input can only be moved forward, output is left as it is
+    fn seek(&mut self, input_position: u64, _output_position: u64) -> bool {
+        while self.values_read < input_position && self.read().is_ok() {}
+        self.values_read == input_position
+    }
+}
+
+// how values on a tape are presented to the outside world
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TapeEncoding {
+    Integer,   // whitespace separated numbers
+    Character  // Unicode code points of characters
+}
+
+// value read for provided character
+pub fn encode_char(character: char) -> ImmediateValue {
+    ImmediateValue::from_address(character as usize)
+}
+
+// character written for provided value, values which are not valid code points give U+FFFD
+pub fn decode_char(value: &ImmediateValue) -> char {
+    value.to_address().and_then(|x| u32::try_from(x).ok()).and_then(char::from_u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+// characters of the last line read, handed out one at a time (line breaks included)
+#[derive(Default)]
+pub struct CharBuffer {
+    characters: VecDeque<char>
+}
+
+impl CharBuffer {
+    pub fn new() -> Self {
+        CharBuffer { characters: VecDeque::new() }
+    }
+
+    // whether the next character needs another line to be read
+    pub fn is_empty(&self) -> bool {
+        self.characters.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.characters.clear();
+    }
+
+    // gives code point of the next character, reading another line from provided reader when the last one is used up
+    pub fn read(&mut self, reader: &mut impl BufRead) -> Result<ImmediateValue, TapeError> {
+        if self.characters.is_empty() {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) => return Err(TapeError::EndOfTape),
+                Ok(_) => self.characters.extend(line.chars()),
+                Err(error) => return Err(TapeError::Io(error.to_string()))
+            }
+        }
+        Ok(encode_char(self.characters.pop_front().unwrap()))
+    }
+}
+
+// reads characters from any reader and writes characters to any writer
+pub struct CharTape<R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+    characters: CharBuffer,
+    values_read: u64
+}
+
+impl<R: BufRead, W: Write> CharTape<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        CharTape {
+            reader,
+            writer,
+            characters: CharBuffer::new(),
+            values_read: 0
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> Tape for CharTape<R, W> {
+    fn read(&mut self) -> Result<ImmediateValue, TapeError> {
+        let value = self.characters.read(&mut self.reader)?;
+        self.values_read += 1;
+        Ok(value)
+    }
+    // flushed on every character, as the emulator may exit without dropping the tape
+    fn write(&mut self, value: ImmediateValue) {
+        let _ = write!(self.writer, "{}", decode_char(&value));
+        let _ = self.writer.flush();
+    }
+    // input can only be moved forward, output is left as it is
+    fn seek(&mut self, input_position: u64, _output_position: u64) -> bool {
+        while self.values_read < input_position && self.read().is_ok() {}
+        self.values_read == input_position
+    }
+}
+
+// reads values from a file and writes them to another file, one per line (or as they are with character encoding)
+pub struct FileTape {
+    input_path: Option<String>,
+    encoding: TapeEncoding,
+    input: Box<dyn Tape>,
+    output: Box<dyn Tape>
+}
+
+impl FileTape {
+    // without input file there is nothing to read, without output file written values are discarded
+    pub fn open(input_path: Option<&str>, output_path: Option<&str>) -> io::Result<Self> {
+        Self::open_with_encoding(input_path, output_path, TapeEncoding::Integer)
+    }
+
+    pub fn open_with_encoding(input_path: Option<&str>, output_path: Option<&str>, encoding: TapeEncoding) -> io::Result<Self> {
+        // flushed on every line, as the emulator may exit without dropping the tape (CharTape flushes every character)
+        let output: Box<dyn Tape> = match (output_path, encoding) {
+            (Some(path), TapeEncoding::Integer) => Box::new(ReaderWriterTape::new(io::empty(), LineWriter::new(File::create(path)?))),
+            (Some(path), TapeEncoding::Character) => Box::new(CharTape::new(io::empty(), File::create(path)?)),
+            (None, _) => Box::new(ReaderWriterTape::new(io::empty(), io::sink()))
+        };
+        Ok(FileTape {
+            input_path: input_path.map(|x| x.to_owned()),
+            encoding,
+            input: Self::open_input(input_path, encoding)?,
+            output
+        })
+    }
+
+    fn open_input(path: Option<&str>, encoding: TapeEncoding) -> io::Result<Box<dyn Tape>> {
+        let reader: Box<dyn BufRead> = match path {
+            Some(path) => Box::new(BufReader::new(File::open(path)?)),
+            None => Box::new(io::empty())
+        };
+        Ok(match encoding {
+            TapeEncoding::Integer => Box::new(ReaderWriterTape::new(reader, io::sink())),
+            TapeEncoding::Character => Box::new(CharTape::new(reader, io::sink()))
+        })
+    }
+}
+
+impl Tape for FileTape {
+    fn read(&mut self) -> Result<ImmediateValue, TapeError> {
+        self.input.read()
+    }
+    fn write(&mut self, value: ImmediateValue) {
+        self.output.write(value)
+    }
+    // input is moved back by reading the file again from the start, output is left as it is
+    fn seek(&mut self, input_position: u64, _output_position: u64) -> bool {
+        if self.input.seek(input_position, 0) { return true; }
+        match Self::open_input(self.input_path.as_deref(), self.encoding) {
+            Ok(input) => self.input = input,
+            Err(_) => return false
+        }
+        self.input.seek(input_position, 0)
+    }
+}
+
+// reads from one tape and writes to another, e.g. input from a file and output to the terminal
+pub struct SplitTape<I: Tape, O: Tape> {
+    input: I,
+    output: O
+}
+
+impl<I: Tape, O: Tape> SplitTape<I, O> {
+    pub fn new(input: I, output: O) -> Self {
+        SplitTape { input, output }
+    }
+}
+
+impl<I: Tape, O: Tape> Tape for SplitTape<I, O> {
+    fn read(&mut self) -> Result<ImmediateValue, TapeError> {
+        self.input.read()
+    }
+    fn write(&mut self, value: ImmediateValue) {
+        self.output.write(value)
+    }
+    // each side is moved only along its own direction
+    fn seek(&mut self, input_position: u64, output_position: u64) -> bool {
+        self.input.seek(input_position, 0) && self.output.seek(0, output_position)
+    }
+}
+
+// tapes indexed by number, plain READ and WRITE use tape 0
+#[derive(Default)]
+pub struct TapeSet {
+    tapes: Vec<Box<dyn Tape>>
+}
+
+impl TapeSet {
+    pub fn new() -> Self {
+        TapeSet { tapes: Vec::new() }
+    }
+
+    // adds tape with the next number, which is returned
+    pub fn add(&mut self, tape: Box<dyn Tape>) -> TapeNumber {
+        self.tapes.push(tape);
+        self.tapes.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.tapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tapes.is_empty()
+    }
+}
+
+impl Tape for TapeSet {
+    fn read(&mut self) -> Result<ImmediateValue, TapeError> {
+        self.read_from(0)
+    }
+    fn write(&mut self, value: ImmediateValue) {
+        let _ = self.write_to(0, value);
+    }
+    fn seek(&mut self, input_position: u64, output_position: u64) -> bool {
+        self.seek_tape(0, input_position, output_position)
+    }
+    fn read_from(&mut self, tape: TapeNumber) -> Result<ImmediateValue, TapeError> {
+        self.tapes.get_mut(tape).ok_or(TapeError::UnknownTape(tape))?.read()
+    }
+    fn write_to(&mut self, tape: TapeNumber, value: ImmediateValue) -> Result<(), TapeError> {
+        self.tapes.get_mut(tape).ok_or(TapeError::UnknownTape(tape))?.write(value);
+        Ok(())
+    }
+    fn seek_tape(&mut self, tape: TapeNumber, input_position: u64, output_position: u64) -> bool {
+        match self.tapes.get_mut(tape) {
+            Some(tape) => tape.seek(input_position, output_position),
+            None => input_position == 0 && output_position == 0
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::instructions::{ImmediateValue, Instruction, InstructionLocation, MemoryLocation};
+use std::io::{self, Write};
+
+// what happened during execution of a single instruction (see Processor::set_tracing)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceRecord {
+    pub step: u64, // number of instructions executed before this one
+    pub instruction_pointer: InstructionLocation,
+    pub instruction: Instruction,
+    pub effective_address: Option<MemoryLocation>,        // address the operand refers to, after indirection
+    pub accumulator_before: ImmediateValue,
+    pub accumulator_after: ImmediateValue,
+    pub write: Option<(MemoryLocation, ImmediateValue)>,   // memory cell written by the instruction and its new value
+    pub input: Option<ImmediateValue>,                     // value read from input tape
+    pub output: Option<ImmediateValue>                     // value written to output tape
+}
+
+impl TraceRecord {
+    pub fn new(step: u64, instruction_pointer: InstructionLocation, instruction: Instruction, accumulator: ImmediateValue) -> Self {
+        TraceRecord {
+            step,
+            instruction_pointer,
+            instruction,
+            effective_address: None,
+            accumulator_before: ImmediateValue::clone(&accumulator),
+            accumulator_after: accumulator,
+            write: None,
+            input: None,
+            output: None
+        }
+    }
+
+    // single line JSON object, missing values are null
+    pub fn to_json(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or("null".to_string());
+        let write = self.write.as_ref().map(|(address, value)| format!("{{\"address\":{},\"value\":{}}}", address, value));
+        format!("{{\"step\":{},\"ip\":{},\"instruction\":\"{}\",\"address\":{},\"acc_before\":{},\"acc_after\":{},\"write\":{},\"input\":{},\"output\":{}}}",
+            self.step, self.instruction_pointer, self.instruction,
+            optional(self.effective_address.map(|x| x.to_string())),
+            self.accumulator_before, self.accumulator_after,
+            optional(write),
+            optional(self.input.as_ref().map(|x| x.to_string())),
+            optional(self.output.as_ref().map(|x| x.to_string())))
+    }
+
+    // CSV row with columns named in CSV_HEADER, missing values are empty
+    pub fn to_csv(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        [
+            self.step.to_string(),
+            self.instruction_pointer.to_string(),
+            self.instruction.to_string(),
+            optional(self.effective_address.map(|x| x.to_string())),
+            self.accumulator_before.to_string(),
+            self.accumulator_after.to_string(),
+            optional(self.write.as_ref().map(|x| x.0.to_string())),
+            optional(self.write.as_ref().map(|x| x.1.to_string())),
+            optional(self.input.as_ref().map(|x| x.to_string())),
+            optional(self.output.as_ref().map(|x| x.to_string()))
+        ].join(",")
+    }
+}
+
+pub const CSV_HEADER: &str = "step,ip,instruction,address,acc_before,acc_after,write_address,write_value,input,output";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    JsonLines,
+    Csv
+}
+
+// writes trace records one per line in provided format
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    format: TraceFormat
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(mut writer: W, format: TraceFormat) -> io::Result<Self> {
+        if format == TraceFormat::Csv {
+            writeln!(writer, "{}", CSV_HEADER)?;
+        }
+        Ok(TraceWriter { writer, format })
+    }
+
+    pub fn write(&mut self, record: &TraceRecord) -> io::Result<()> {
+        match self.format {
+            TraceFormat::JsonLines => writeln!(self.writer, "{}", record.to_json()),
+            TraceFormat::Csv => writeln!(self.writer, "{}", record.to_csv())
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,111 @@
+use crate::bignum::BigInt;
+use crate::instructions::MemoryLocation;
+use crate::processor::OverflowMode;
+use std::{fmt::{Debug, Display, LowerHex}, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithmeticOperation {
+    Add,
+    Sub,
+    Mult,
+    Div
+}
+
+// operations the machine needs from the type stored in memory cells (see ImmediateValue)
+pub trait Value: Clone + Debug + Default + Display + LowerHex + PartialEq + PartialOrd + FromStr {
+    fn from_address(address: MemoryLocation) -> Self;
+    fn to_address(&self) -> Option<MemoryLocation>; // None if value is negative or does not fit
+    fn is_zero(&self) -> bool;
+    fn is_negative(&self) -> bool;
+    fn is_positive(&self) -> bool;
+    fn bit_length(&self) -> u64; // number of bits needed to represent absolute value, 0 for zero
+    // result of arithmetic operation, None on overflow (only possible in OverflowMode::Trap) or division by zero
+    fn calculate(&self, operation: ArithmeticOperation, other: &Self, overflow_mode: OverflowMode) -> Option<Self>;
+}
+
+impl Value for i64 {
+    fn from_address(address: MemoryLocation) -> Self {
+        address as i64
+    }
+
+    fn to_address(&self) -> Option<MemoryLocation> {
+        MemoryLocation::try_from(*self).ok()
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    fn is_negative(&self) -> bool {
+        *self < 0
+    }
+
+    fn is_positive(&self) -> bool {
+        *self > 0
+    }
+
+    fn bit_length(&self) -> u64 {
+        (u64::BITS - self.unsigned_abs().leading_zeros()) as u64
+    }
+
+    fn calculate(&self, operation: ArithmeticOperation, other: &Self, overflow_mode: OverflowMode) -> Option<Self> {
+        let (lhs, rhs) = (*self, *other);
+        if operation == ArithmeticOperation::Div && rhs == 0 { return None; }
+        match overflow_mode {
+            OverflowMode::Trap => match operation {
+                ArithmeticOperation::Add => lhs.checked_add(rhs),
+                ArithmeticOperation::Sub => lhs.checked_sub(rhs),
+                ArithmeticOperation::Mult => lhs.checked_mul(rhs),
+                ArithmeticOperation::Div => lhs.checked_div(rhs)
+            },
+            OverflowMode::Wrap => Some(match operation {
+                ArithmeticOperation::Add => lhs.wrapping_add(rhs),
+                ArithmeticOperation::Sub => lhs.wrapping_sub(rhs),
+                ArithmeticOperation::Mult => lhs.wrapping_mul(rhs),
+                ArithmeticOperation::Div => lhs.wrapping_div(rhs)
+            }),
+            OverflowMode::Saturate => Some(match operation {
+                ArithmeticOperation::Add => lhs.saturating_add(rhs),
+                ArithmeticOperation::Sub => lhs.saturating_sub(rhs),
+                ArithmeticOperation::Mult => lhs.saturating_mul(rhs),
+                ArithmeticOperation::Div => lhs.saturating_div(rhs)
+            })
+        }
+    }
+}
+
+// unbounded integers never overflow, so overflow mode does not matter
+impl Value for BigInt {
+    fn from_address(address: MemoryLocation) -> Self {
+        BigInt::from(address)
+    }
+
+    fn to_address(&self) -> Option<MemoryLocation> {
+        self.to_u64().and_then(|x| MemoryLocation::try_from(x).ok())
+    }
+
+    fn is_zero(&self) -> bool {
+        BigInt::is_zero(self)
+    }
+
+    fn is_negative(&self) -> bool {
+        BigInt::is_negative(self)
+    }
+
+    fn is_positive(&self) -> bool {
+        BigInt::is_positive(self)
+    }
+
+    fn bit_length(&self) -> u64 {
+        BigInt::bit_length(self)
+    }
+
+    fn calculate(&self, operation: ArithmeticOperation, other: &Self, _overflow_mode: OverflowMode) -> Option<Self> {
+        match operation {
+            ArithmeticOperation::Add => Some(self + other),
+            ArithmeticOperation::Sub => Some(self - other),
+            ArithmeticOperation::Mult => Some(self * other),
+            ArithmeticOperation::Div => self.checked_div(other)
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::instructions::{ImmediateValue, Instruction, InstructionLocation, MemoryLocation};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual
+}
+
+impl Comparison {
+    pub fn evaluate(&self, lhs: &ImmediateValue, rhs: &ImmediateValue) -> bool {
+        match self {
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+            Comparison::Equal => lhs == rhs,
+            Comparison::NotEqual => lhs != rhs,
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">="
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchCondition {
+    Read,                                // any read of the cell
+    Write,                               // any write to the cell
+    Value(Comparison, ImmediateValue)    // write storing value satisfying condition, e.g. mem[4] < 0
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchpoint {
+    pub address: MemoryLocation,
+    pub condition: WatchCondition
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.condition {
+            WatchCondition::Read => write!(f, "read of [{}]", self.address),
+            WatchCondition::Write => write!(f, "write to [{}]", self.address),
+            WatchCondition::Value(comparison, value) => write!(f, "[{}] {} {}", self.address, comparison.symbol(), value)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Access {
+    Read,
+    Write
+}
+
+// memory access which triggered a watchpoint
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchHit {
+    pub watchpoint: usize, // index of the watchpoint
+    pub access: Access,
+    pub address: MemoryLocation,
+    pub instruction_pointer: InstructionLocation,
+    pub instruction: Instruction,
+    pub old_value: ImmediateValue,
+    pub new_value: ImmediateValue // the same as old value for reads
+}
@@ -1,52 +1,416 @@
 use std::process::exit;
 use std::env::{args, self};
-use std::io::{stdin, stdout, BufRead, Write};
-use ram_machine::processor::Processor;
-use ram_machine::tape::Tape;
-use ram_machine::parser::parse_input;
+use std::fs::{self, File};
+use std::io::{self, stdin, stdout, BufRead, BufReader, BufWriter, Write};
+use std::time::{Duration, Instant};
+use ram_machine::processor::{Processor, OverflowMode, MemoryPolicy, EofPolicy};
+use ram_machine::tape::{self, CharBuffer, CharTape, FileTape, ReaderWriterTape, SplitTape, Tape, TapeEncoding, TapeSet, VecTape};
+use ram_machine::memory::Memory;
+use ram_machine::instructions::{ImmediateValue, TapeNumber};
+use ram_machine::parser::parse_str;
+use ram_machine::error::{ExecutionErrorKind, ParseError, ParseErrorKind, TapeError};
+use ram_machine::program::Program;
+use ram_machine::snapshot::Snapshot;
+use ram_machine::trace::{TraceFormat, TraceWriter};
+use ram_machine::profile::Profile;
+use debugger::Debugger;
+
+mod debugger;
 
 struct StdTape {
+    tape: TapeNumber,
+    encoding: TapeEncoding,
+    characters: CharBuffer, // not yet read characters of the last entered line (character encoding only)
     current_input: usize,
     current_output: usize
 }
 
 impl StdTape {
-    fn new() -> Self {
+    fn new(tape: TapeNumber, encoding: TapeEncoding) -> Self {
         Self {
+            tape,
+            encoding,
+            characters: CharBuffer::new(),
             current_input: 0,
             current_output: 0
         }
     }
+
+    // tape number is shown only for tapes other than 0, e.g. [inp@1:0]
+    fn prompt(&self, kind: &str, position: usize) -> String {
+        match self.tape {
+            0 => format!("[{}:{}]", kind, position),
+            tape => format!("[{}@{}:{}]", kind, tape, position)
+        }
+    }
 }
 
 impl Tape for StdTape {
-    fn read(&mut self) -> Option<i64> {
-        print!("[inp:{}] < ", self.current_input);
-        self.current_input += 1;
-        if let Err(_) = stdout().flush() { }
-        match stdin().lock().lines().next() {
-            Some(result) => {
-                match result {
-                    Ok(read_line) => {
-                        match read_line.trim().parse::<i64>() {
-                            Ok(value) => { return Some(value); },
-                            Err(_) => { return None }
-                        }
+    // asks again until a valid value is entered, with character encoding asks only when the entered line was used up
+    fn read(&mut self) -> Result<ImmediateValue, TapeError> {
+        if self.encoding == TapeEncoding::Character {
+            if self.characters.is_empty() {
+                print!("{} < ", self.prompt("inp", self.current_input));
+                let _ = stdout().flush();
+            }
+            let value = self.characters.read(&mut stdin().lock())?;
+            self.current_input += 1;
+            return Ok(value);
+        }
+        loop {
+            print!("{} < ", self.prompt("inp", self.current_input));
+            let _ = stdout().flush();
+            let read_line = match stdin().lock().lines().next() {
+                Some(Ok(read_line)) => read_line,
+                Some(Err(error)) => return Err(TapeError::Io(error.to_string())),
+                None => return Err(TapeError::EndOfTape)
+            };
+            match read_line.trim().parse::<ImmediateValue>() {
+                Ok(value) => {
+                    self.current_input += 1;
+                    return Ok(value);
+                },
+                Err(_) => println!("error: `{}` is not a valid value, try again", read_line.trim())
+            }
+        }
+    }
+    // characters are printed as they are, without prompt
+    fn write(&mut self, value: ImmediateValue) {
+        match self.encoding {
+            TapeEncoding::Integer => println!("{} > {}", self.prompt("out", self.current_output), value),
+            TapeEncoding::Character => {
+                print!("{}", tape::decode_char(&value));
+                let _ = stdout().flush();
+            }
+        }
+        self.current_output += 1;
+    }
+    // values are entered again by the user, so only the prompts have to follow
+    fn seek(&mut self, input_position: u64, output_position: u64) -> bool {
+        self.current_input = input_position as usize;
+        self.current_output = output_position as usize;
+        self.characters.clear();
+        true
+    }
+}
+
+struct Options {
+    filename: Option<String>, // may be omitted when resuming from snapshot
+    resume: Option<String>,
+    snapshot: Option<String>,
+    trace: Option<String>,
+    memory_size: usize,
+    sparse_memory: bool,
+    overflow_mode: OverflowMode,
+    memory_policy: MemoryPolicy,
+    eof_policy: Option<EofPolicy>, // None means the default, which depends on tape encoding
+    print_cost: bool,
+    profile: bool,
+    step_limit: Option<u64>,
+    timeout: Option<Duration>,
+    debugger: bool,
+    history_limit: usize,
+    input: Vec<(TapeNumber, String)>,
+    input_values: Vec<(TapeNumber, String)>, // comma separated values, or text with character encoding
+    output: Vec<(TapeNumber, String)>,
+    encodings: Vec<(TapeNumber, TapeEncoding)>,
+    quiet: bool
+}
+
+const DEFAULT_MEMORY_LIMIT: usize = 1 << 20;
+
+// number of steps the debugger can undo by default
+const DEFAULT_HISTORY_LIMIT: usize = 100_000;
+
+// exit code used when execution was stopped by --max-steps or --timeout
+const EXIT_LIMIT_EXCEEDED: i32 = 3;
+
+fn print_usage(program_name: String, quiet: bool) {
+    let usage = format!(concat!(
+        "usage: ./{0} [source_code.rasm] <memory size> [options]\n",
+        "       ./{0} --resume <snapshot> [source_code.rasm] [options]\n",
+        "options:\n",
+        "  --quiet                          batch mode: read input silently from stdin, print output values one per line, no banners\n",
+        "  --resume <file>                  restore the machine from snapshot and continue execution\n",
+        "  --snapshot <file>                save snapshot of the machine when execution stops\n",
+        "  --input [n=]<file>               read input tape n (default: 0) from file (whitespace separated values)\n",
+        "  --input-values [n=]<v1,v2,...>   read input tape n (default: 0) from provided values (or text with char encoding)\n",
+        "  --output [n=]<file>              write output tape n (default: 0) to file (one value per line)\n",
+        "  --encoding [n=]<int|char>        encoding of tape n (default: 0): numbers or characters as code points (default: int)\n",
+        "  --trace <file>                   write record of every executed instruction to file (CSV if it ends with .csv, JSON Lines otherwise)\n",
+        "  --debug                          run the program in interactive debugger\n",
+        "  --history <steps>                number of steps the debugger can undo (default: {1})\n",
+        "  --sparse                         use unbounded sparse memory instead of fixed size one\n",
+        "  --cost                           print time and space cost of the program after it halts\n",
+        "  --profile                        print execution counts of instructions and labels after the run\n",
+        "  --max-steps <count>              stop the program after executing provided number of instructions\n",
+        "  --timeout <seconds>              stop the program after running for provided time\n",
+        "  --overflow <trap|wrap|saturate>  behavior of arithmetic on overflow (default: trap)\n",
+        "  --memory-policy <strict|lenient|grow[:limit]>\n",
+        "                                   behavior of accesses past the end of memory (default: lenient)\n",
+        "  --eof <error|halt|flag|sentinel[:value]>\n",
+        "                                   behavior of read past the end of input (default: error, sentinel:-1 with char encoding)"
+    ), program_name, DEFAULT_HISTORY_LIMIT);
+    report(&usage, quiet);
+}
+
+fn parse_arguments(arguments: &[String]) -> Result<Options, String> {
+    let mut positional = Vec::<String>::new();
+    let mut overflow_mode = OverflowMode::Trap;
+    let mut memory_policy = MemoryPolicy::Lenient;
+    let mut eof_policy: Option<EofPolicy> = None;
+    let mut sparse_memory = false;
+    let mut print_cost = false;
+    let mut profile = false;
+    let mut step_limit: Option<u64> = None;
+    let mut timeout: Option<Duration> = None;
+    let mut debugger = false;
+    let mut history_limit = DEFAULT_HISTORY_LIMIT;
+    let mut input = Vec::<(TapeNumber, String)>::new();
+    let mut input_values = Vec::<(TapeNumber, String)>::new();
+    let mut output = Vec::<(TapeNumber, String)>::new();
+    let mut encodings = Vec::<(TapeNumber, TapeEncoding)>::new();
+    let mut quiet = false;
+    let mut resume: Option<String> = None;
+    let mut snapshot: Option<String> = None;
+    let mut trace: Option<String> = None;
+    let mut iterator = arguments.iter().skip(1);
+    while let Some(argument) = iterator.next() {
+        match argument.as_str() {
+            "--debug" => debugger = true,
+            "--quiet" => quiet = true,
+            "--sparse" => sparse_memory = true,
+            "--cost" => print_cost = true,
+            "--profile" => profile = true,
+            "--resume" => resume = Some(iterator.next().ok_or("no snapshot file provided")?.to_owned()),
+            "--snapshot" => snapshot = Some(iterator.next().ok_or("no snapshot file provided")?.to_owned()),
+            "--input" => {
+                let (tape, path) = split_tape_number(iterator.next().ok_or("no input file provided")?);
+                input.push((tape, path.to_owned()));
+            },
+            "--input-values" => {
+                let (tape, values) = split_tape_number(iterator.next().ok_or("no input values provided")?);
+                input_values.push((tape, values.to_owned()));
+            },
+            "--encoding" => {
+                let (tape, encoding) = split_tape_number(iterator.next().ok_or("no tape encoding provided")?);
+                let encoding = match encoding {
+                    "int" => TapeEncoding::Integer,
+                    "char" => TapeEncoding::Character,
+                    _ => return Err(format!("unknown tape encoding {}", encoding))
+                };
+                encodings.retain(|x| x.0 != tape);
+                encodings.push((tape, encoding));
+            },
+            "--output" => {
+                let (tape, path) = split_tape_number(iterator.next().ok_or("no output file provided")?);
+                output.push((tape, path.to_owned()));
+            },
+            "--trace" => trace = Some(iterator.next().ok_or("no trace file provided")?.to_owned()),
+            "--history" => {
+                history_limit = match iterator.next().map(|x| x.parse::<usize>()) {
+                    Some(Ok(value)) => value,
+                    Some(Err(_)) => return Err("provided history size is incorrect".to_string()),
+                    None => return Err("no history size provided".to_string())
+                }
+            },
+            "--max-steps" => {
+                step_limit = match iterator.next().map(|x| x.parse::<u64>()) {
+                    Some(Ok(value)) => Some(value),
+                    Some(Err(_)) => return Err("provided step limit is incorrect".to_string()),
+                    None => return Err("no step limit provided".to_string())
+                }
+            },
+            "--timeout" => {
+                timeout = match iterator.next().map(|x| x.parse::<f64>()) {
+                    Some(Ok(value)) if value.is_finite() && value > 0.0 => Some(Duration::from_secs_f64(value)),
+                    Some(_) => return Err("provided timeout is incorrect".to_string()),
+                    None => return Err("no timeout provided".to_string())
+                }
+            },
+            "--overflow" => {
+                overflow_mode = match iterator.next().map(|x| x.as_str()) {
+                    Some("trap") => OverflowMode::Trap,
+                    Some("wrap") => OverflowMode::Wrap,
+                    Some("saturate") => OverflowMode::Saturate,
+                    Some(value) => return Err(format!("unknown overflow mode {}", value)),
+                    None => return Err("no overflow mode provided".to_string())
+                }
+            },
+            "--memory-policy" => {
+                memory_policy = match iterator.next().map(|x| x.as_str()) {
+                    Some("strict") => MemoryPolicy::Strict,
+                    Some("lenient") => MemoryPolicy::Lenient,
+                    Some("grow") => MemoryPolicy::AutoGrow(DEFAULT_MEMORY_LIMIT),
+                    Some(value) if value.starts_with("grow:") => match value["grow:".len()..].parse::<usize>() {
+                        Ok(limit) => MemoryPolicy::AutoGrow(limit),
+                        Err(_) => return Err(format!("provided memory limit in {} is incorrect", value))
                     },
-                    Err(_) => { return None }
+                    Some(value) => return Err(format!("unknown memory policy {}", value)),
+                    None => return Err("no memory policy provided".to_string())
                 }
             },
-            None => { return None }
+            "--eof" => {
+                eof_policy = Some(match iterator.next().map(|x| x.as_str()) {
+                    Some("error") => EofPolicy::Error,
+                    Some("halt") => EofPolicy::Halt,
+                    Some("flag") => EofPolicy::Flag,
+                    Some("sentinel") => EofPolicy::Sentinel(ImmediateValue::default()),
+                    Some(value) if value.starts_with("sentinel:") => match value["sentinel:".len()..].parse::<ImmediateValue>() {
+                        Ok(sentinel) => EofPolicy::Sentinel(sentinel),
+                        Err(_) => return Err(format!("provided sentinel value in {} is incorrect", value))
+                    },
+                    Some(value) => return Err(format!("unknown end of input policy {}", value)),
+                    None => return Err("no end of input policy provided".to_string())
+                })
+            },
+            _ if argument.starts_with("--") => return Err(format!("unknown option {}", argument)),
+            _ => positional.push(argument.to_owned())
         }
     }
-    fn write(&mut self, value: i64) {
-        println!("[out:{}] > {}", self.current_output, value);
-        self.current_output += 1;
+
+    if (positional.is_empty() && resume.is_none()) || positional.len() > 2 {
+        return Err(String::new());
+    }
+    let mut input_tapes: Vec<TapeNumber> = input.iter().map(|x| x.0).chain(input_values.iter().map(|x| x.0)).collect();
+    input_tapes.sort();
+    if let Some(tape) = input_tapes.windows(2).find(|x| x[0] == x[1]) {
+        return Err(format!("input tape {} is provided more than once", tape[0]));
+    }
+    if let Some((_, values)) = input_values.iter().find(|x| tape_encoding(&encodings, x.0) == TapeEncoding::Integer && parse_values(&x.1).is_none()) {
+        return Err(format!("provided input values {} are incorrect", values));
+    }
+    let mut output_tapes: Vec<TapeNumber> = output.iter().map(|x| x.0).collect();
+    output_tapes.sort();
+    if let Some(tape) = output_tapes.windows(2).find(|x| x[0] == x[1]) {
+        return Err(format!("output tape {} is provided more than once", tape[0]));
+    }
+    if quiet && debugger {
+        return Err("--quiet and --debug cannot be used together".to_string());
+    }
+    let filename = positional.first().cloned();
+    let memory_size: usize = if positional.len() == 2 {
+        let memory_size_string = positional[1].to_owned();
+        match memory_size_string.parse::<usize>() {
+            Ok(value) => {
+                if value == 0 {
+                    return Err("provided memory size cannot be 0".to_string());
+                }
+                value
+            },
+            Err(_) => return Err(format!("provided memory size of {} is incorrect", memory_size_string))
+        }
+    } else { 512 };
+
+    Ok(Options {
+        filename,
+        resume,
+        snapshot,
+        trace,
+        memory_size,
+        sparse_memory,
+        overflow_mode,
+        memory_policy,
+        eof_policy,
+        print_cost,
+        profile,
+        step_limit,
+        timeout,
+        debugger,
+        history_limit,
+        input,
+        input_values,
+        output,
+        encodings,
+        quiet
+    })
+}
+
+// splits optional tape number from option value, e.g. 1=data.txt, values without it refer to tape 0
+fn split_tape_number(value: &str) -> (TapeNumber, &str) {
+    match value.split_once('=') {
+        Some((tape, rest)) => match tape.parse::<TapeNumber>() {
+            Ok(tape) => (tape, rest),
+            Err(_) => (0, value)
+        },
+        None => (0, value)
+    }
+}
+
+fn parse_values(values: &str) -> Option<Vec<ImmediateValue>> {
+    values.split(',').filter(|x| !x.trim().is_empty()).map(|x| x.trim().parse::<ImmediateValue>().ok()).collect()
+}
+
+fn tape_encoding(encodings: &[(TapeNumber, TapeEncoding)], tape: TapeNumber) -> TapeEncoding {
+    encodings.iter().find(|x| x.0 == tape).map(|x| x.1).unwrap_or(TapeEncoding::Integer)
+}
+
+// input and output tapes are interactive (or plain stdin and stdout in quiet mode) unless redirected to files or provided values,
+// in quiet mode only tape 0 reads stdin, other unbound input tapes are empty
+fn open_tapes(options: &Options, tape_count: usize) -> TapeSet {
+    let mut tapes = TapeSet::new();
+    for tape in 0..tape_count {
+        let encoding = tape_encoding(&options.encodings, tape);
+        let input_path = options.input.iter().find(|x| x.0 == tape).map(|x| x.1.as_str());
+        let input_values = options.input_values.iter().find(|x| x.0 == tape).map(|x| x.1.as_str());
+        let input_tape: Box<dyn Tape> = match (input_path, input_values, &encoding) {
+            (Some(path), _, _) => open_file_tape(FileTape::open_with_encoding(Some(path), None, encoding), path, "read", options.quiet),
+            (None, Some(values), TapeEncoding::Integer) => Box::new(VecTape::new(parse_values(values).unwrap_or_default())),
+            (None, Some(text), TapeEncoding::Character) => Box::new(VecTape::new(text.chars().map(tape::encode_char).collect())),
+            (None, None, TapeEncoding::Integer) if options.quiet && tape == 0 => Box::new(ReaderWriterTape::new(BufReader::new(stdin()), io::sink())),
+            (None, None, TapeEncoding::Character) if options.quiet && tape == 0 => Box::new(CharTape::new(BufReader::new(stdin()), io::sink())),
+            (None, None, TapeEncoding::Integer) if options.quiet => Box::new(VecTape::new(Vec::new())),
+            (None, None, TapeEncoding::Character) if options.quiet => Box::new(CharTape::new(io::empty(), io::sink())),
+            (None, None, _) => Box::new(StdTape::new(tape, encoding))
+        };
+        let output_path = options.output.iter().find(|x| x.0 == tape).map(|x| x.1.as_str());
+        let output_tape: Box<dyn Tape> = match (output_path, &encoding) {
+            (Some(path), _) => open_file_tape(FileTape::open_with_encoding(None, Some(path), encoding), path, "write", options.quiet),
+            (None, TapeEncoding::Integer) if options.quiet => Box::new(ReaderWriterTape::new(io::empty(), stdout())),
+            (None, TapeEncoding::Character) if options.quiet => Box::new(CharTape::new(io::empty(), stdout())),
+            (None, _) => Box::new(StdTape::new(tape, encoding))
+        };
+        tapes.add(Box::new(SplitTape::new(input_tape, output_tape)));
     }
+    tapes
 }
 
-fn print_usage(program_name: String) {
-    println!("usage: ./{} [source_code.rasm] <memory size>", program_name)
+fn open_file_tape<T: Tape + 'static>(tape: io::Result<T>, path: &str, purpose: &str, quiet: bool) -> Box<dyn Tape> {
+    match tape {
+        Ok(tape) => Box::new(tape),
+        Err(_) => {
+            report(&format!("error: could not open \"{}\" file to {}", path, purpose), quiet);
+            exit(1);
+        }
+    }
+}
+
+// in quiet mode errors and summaries go to stderr, so that stdout holds only the output tape
+fn report(message: &str, quiet: bool) {
+    if quiet {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+fn save_snapshot<T: Tape>(processor: &Processor<T>, path: &Option<String>, quiet: bool) {
+    if let Some(path) = path {
+        match processor.snapshot().save(path) {
+            Ok(()) => if !quiet { println!("info: snapshot saved to {}", path) },
+            Err(error) => report(&format!("snapshot error: {}", error), quiet)
+        }
+    }
+}
+
+fn print_profile(profile: &Profile, program: &Program, source: &str, quiet: bool) {
+    let total = profile.total().max(1);
+    let mut lines = vec![format!("info: profile ({} instructions executed):", profile.total())];
+    for (label, count) in profile.by_label(program) {
+        lines.push(format!("  {:>10} {:>6.2}%  {}", count, count as f64 * 100.0 / total as f64, label.unwrap_or("(before the first label)")));
+    }
+    lines.push(profile.annotate(program, source));
+    report(&lines.join("\n"), quiet);
 }
 
 fn main() {
@@ -55,51 +419,180 @@ fn main() {
     let debug_mode = debug_var.is_some();
 
     let arguments: Vec<String> = args().collect();
-    if arguments.len() < 2 {
-        print_usage(arguments[0].to_owned());
+    let options = match parse_arguments(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            // options were not parsed, but errors should still keep out of stdout in quiet mode
+            let quiet = arguments.iter().any(|x| x == "--quiet");
+            if !message.is_empty() {
+                report(&format!("error: {}", message), quiet);
+            }
+            print_usage(arguments[0].to_owned(), quiet);
+            exit(1);
+        }
+    };
+    let memory_size = options.memory_size;
+
+    let snapshot = options.resume.as_ref().map(|path| match Snapshot::load(path) {
+        Ok(snapshot) => snapshot,
+        Err(error) => {
+            report(&format!("snapshot error: {}", error), options.quiet);
+            exit(1);
+        }
+    });
+    let (program, source) = match (&options.filename, &snapshot) {
+        (Some(filename), _) => {
+            let source = match fs::read_to_string(filename) {
+                Ok(source) => source,
+                Err(_) => {
+                    report(&ParseError::new(ParseErrorKind::FileOpenFailed, 0, 0..0, filename).render(filename), options.quiet);
+                    exit(1);
+                }
+            };
+            match parse_str(&source) {
+                Ok(program) => (program, source),
+                Err(errors) => {
+                    for error in errors.iter() {
+                        report(&error.render(filename), options.quiet);
+                    }
+                    exit(1);
+                }
+            }
+        },
+        // without source code, instructions from snapshot (one per line) serve as one
+        (None, Some(snapshot)) => {
+            let program = Program {
+                instructions: snapshot.instructions.clone(),
+                source_lines: (1..=snapshot.instructions.len()).collect(),
+                labels: Vec::new(),
+                symbols: Vec::new(),
+                data: Vec::new()
+            };
+            let source = snapshot.instructions.iter().map(|x| x.to_string()).collect::<Vec<String>>().join("\n");
+            (program, source)
+        },
+        (None, None) => unreachable!()
+    };
+    if snapshot.as_ref().is_some_and(|x| x.instructions != program.instructions) {
+        report("error: snapshot was taken from a different program", options.quiet);
         exit(1);
     }
-    let filename = arguments[1].to_owned();
-    let memory_size: usize = if arguments.len() == 3 {
-        let memory_size_string = arguments[2].to_owned();
-        match memory_size_string.parse::<usize>() {
-            Ok(value) => {
-                if value == 0 {
-                    println!("error: provided memory size cannot be 0");
+
+    let memory = if let Some(snapshot) = &snapshot {
+        if !options.quiet { println!("info: resuming after {} executed instructions", snapshot.step_count); }
+        Memory::new(1) // replaced when restoring snapshot
+    } else if options.sparse_memory {
+        if !options.quiet { println!("info: loaded {} instructions, memory size: unbounded (sparse)", program.instructions.len()); }
+        Memory::new_sparse()
+    } else {
+        // memory which may grow is made big enough to hold initial data right away
+        let memory_size = match options.memory_policy {
+            MemoryPolicy::AutoGrow(limit) if program.data_end() > memory_size => {
+                if program.data_end() > limit {
+                    report(&format!("error: initial data needs {} memory cells, but memory can grow only up to {}", program.data_end(), limit), options.quiet);
                     exit(1);
                 }
-                value
+                program.data_end()
             },
+            _ => memory_size
+        };
+        if !options.quiet { println!("info: loaded {} instructions, memory size: {} cells", program.instructions.len(), memory_size); }
+        Memory::new(memory_size)
+    };
+    // memory restored from snapshot already holds everything
+    let data = if snapshot.is_some() { Vec::new() } else { program.data.clone() };
+    // every tape used by the program or bound by options, at least tape 0
+    let tape_count = program.instructions.iter().filter_map(|x| x.tape())
+        .chain(options.input.iter().map(|x| x.0))
+        .chain(options.input_values.iter().map(|x| x.0))
+        .chain(options.output.iter().map(|x| x.0))
+        .chain(options.encodings.iter().map(|x| x.0))
+        .max().unwrap_or(0) + 1;
+    let mut processor = match Processor::new(program.instructions.clone(), memory, data, open_tapes(&options, tape_count)) {
+        Ok(processor) => processor,
+        Err(error) => {
+            report(&format!("error: {}", error), options.quiet);
+            exit(1);
+        }
+    };
+    if let Some(snapshot) = snapshot {
+        for tape in processor.restore(snapshot) {
+            report(&format!("warning: tape {} could not be moved to the position recorded in snapshot, so it continues from a different value", tape), options.quiet);
+        }
+    }
+    processor.set_overflow_mode(options.overflow_mode);
+    processor.set_memory_policy(options.memory_policy);
+    match &options.eof_policy {
+        Some(eof_policy) => processor.set_eof_policy(eof_policy.clone()),
+        // text has no natural end marker, so character tapes read -1 past the end unless told otherwise
+        None => for (tape, _) in options.encodings.iter().filter(|x| x.1 == TapeEncoding::Character) {
+            processor.set_tape_eof_policy(*tape, Some(EofPolicy::Sentinel(ImmediateValue::from(-1i64))));
+        }
+    }
+    // step limit counts instructions executed in this run
+    processor.set_step_limit(options.step_limit.map(|x| x + processor.get_step_count()));
+    if options.debugger {
+        processor.set_history_limit(options.history_limit);
+        Debugger::new(&program, &source).run(&mut processor);
+        return;
+    }
+    let mut trace_writer = options.trace.as_ref().map(|path| {
+        let format = if path.ends_with(".csv") { TraceFormat::Csv } else { TraceFormat::JsonLines };
+        match File::create(path).and_then(|file| TraceWriter::new(BufWriter::new(file), format)) {
+            Ok(writer) => writer,
             Err(_) => {
-                println!("error: provided memory size of {} is incorrect", memory_size_string);
+                report(&format!("error: could not create \"{}\" trace file", path), options.quiet);
                 exit(1);
             }
         }
-    } else { 512 };
-
-    let parse_result = parse_input(&filename);
-    if let Err(message) = parse_result {
-        println!("parser error: {}", message);
-        exit(1);
-    }
-
-    let instructions = parse_result.unwrap();
-    println!("info: loaded {} instructions, memory size: {} cells", instructions.len(), memory_size);
-    let mut processor = Processor::new(
-        instructions, 
-        memory_size,
-        StdTape::new()
-    );
+    });
+    processor.set_tracing(trace_writer.is_some());
+    processor.set_profiling(options.profile);
+    let start_time = Instant::now();
+    let mut exit_code = 0;
     while !processor.is_halted() {
+        if let Some(timeout) = options.timeout {
+            if start_time.elapsed() > timeout {
+                report(&format!("execution error: time limit of {}s exceeded", timeout.as_secs_f64()), options.quiet);
+                exit_code = EXIT_LIMIT_EXCEEDED;
+                break;
+            }
+        }
         if debug_mode {
             let state = processor.get_current_state();
             println!("debug: {:?} @ address {}", state.0, state.1);
         }
-        if let Err(message) = processor.execute_instruction() {
-            println!("execution error: {}", message);
-            exit(1);
+        let result = processor.execute_instruction();
+        if let (Some(writer), Some(record)) = (&mut trace_writer, processor.take_trace_record()) {
+            if writer.write(&record).is_err() {
+                report("error: writing trace failed", options.quiet);
+                exit(1);
+            }
+        }
+        if let Err(error) = result {
+            report(&format!("execution error: {}", error), options.quiet);
+            exit_code = match error.kind {
+                ExecutionErrorKind::StepLimitExceeded(_) => EXIT_LIMIT_EXCEEDED,
+                _ => 1
+            };
+            break;
         }
     }
+    // buffered trace has to be flushed explicitly, as exit() does not run destructors
+    if trace_writer.as_mut().is_some_and(|writer| writer.flush().is_err()) {
+        report("error: writing trace failed", options.quiet);
+    }
+    save_snapshot(&processor, &options.snapshot, options.quiet);
+    if let Some(profile) = processor.get_profile() {
+        print_profile(profile, &program, &source, options.quiet);
+    }
+    if exit_code != 0 {
+        exit(exit_code);
+    }
+    if options.print_cost {
+        let cost = processor.get_cost();
+        report(&format!("info: cost (uniform / logarithmic criterion):\n  time:  {} / {}\n  space: {} / {}",
+            cost.uniform_time, cost.logarithmic_time, cost.uniform_space, cost.logarithmic_space), options.quiet);
+    }
     // processor.dump();
 }
-